@@ -3,5 +3,5 @@
 echo ">> Building contract"
 
 rustup target add wasm32-unknown-unknown
-cargo build --all --target wasm32-unknown-unknown --release
-cp ./target/wasm32-unknown-unknown/release/contract.wasm ../out/main.wasm
\ No newline at end of file
+cargo build -p contract --target wasm32-unknown-unknown --profile contract
+cp ../target/wasm32-unknown-unknown/contract/contract.wasm ../out/main.wasm
\ No newline at end of file
@@ -0,0 +1,109 @@
+use crate::evidence::ResponseEvidence;
+use crate::*;
+use ed25519_dalek::Verifier;
+use near_sdk::CurveType;
+
+pub use near_oracle_types::Attestation;
+
+/// Borsh encoding of `(id, return_value, timestamp)`, the bytes every signed fulfilment binds.
+pub fn signing_payload(id: &str, return_value: &str, timestamp: u64) -> Vec<u8> {
+    (id, return_value, timestamp).try_to_vec().unwrap()
+}
+
+/// Message a provider signs: sha256 of the [`signing_payload`]. When the fulfilment carries
+/// evidence, the sha256 of its Borsh encoding is appended to the payload first, so a relayer
+/// cannot attach evidence the provider did not sign.
+pub fn attestation_message(
+    id: &str,
+    return_value: &str,
+    timestamp: u64,
+    evidence: Option<&ResponseEvidence>,
+) -> Vec<u8> {
+    let mut payload = signing_payload(id, return_value, timestamp);
+    if let Some(evidence) = evidence {
+        payload.extend_from_slice(&env::sha256(&evidence.try_to_vec().unwrap()));
+    }
+    env::sha256(&payload)
+}
+
+/// near-sdk 4.1 has no `env::ed25519_verify` host function, so verification runs in-contract.
+pub fn ed25519_verify(signature: &[u8], message: &[u8], public_key: &[u8]) -> bool {
+    let (signature, public_key) = match (
+        ed25519_dalek::Signature::try_from(signature),
+        ed25519_dalek::PublicKey::from_bytes(public_key),
+    ) {
+        (Ok(signature), Ok(public_key)) => (signature, public_key),
+        _ => return false,
+    };
+    public_key.verify(message, &signature).is_ok()
+}
+
+//owner
+#[near_bindgen]
+impl Contract {
+    pub fn add_data_provider(&mut self, public_key: PublicKey) -> bool {
+        self.assert_owner();
+        require!(
+            public_key.curve_type() == CurveType::ED25519,
+            "ERR_UNSUPPORTED_KEY_TYPE"
+        );
+        self.data_providers.insert(&public_key)
+    }
+    /// Randomness requests assigned to a removed provider can be cancelled by their
+    /// requesters with `cancel_randomness`.
+    pub fn remove_data_provider(&mut self, public_key: PublicKey) -> bool {
+        self.assert_owner();
+        self.data_providers.remove(&public_key)
+    }
+}
+//view
+#[near_bindgen]
+impl Contract {
+    pub fn is_data_provider(&self, public_key: PublicKey) -> bool {
+        self.data_providers.contains(&public_key)
+    }
+    pub fn get_data_providers(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<PublicKey> {
+        self.data_providers
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .collect()
+    }
+}
+impl Contract {
+    pub fn assert_valid_attestation(
+        &self,
+        oracle_data: &OracleData,
+        return_value: &str,
+        attestation: &Attestation,
+        evidence: Option<&ResponseEvidence>,
+    ) {
+        require!(
+            self.data_providers.contains(&attestation.public_key),
+            "ERR_UNKNOWN_DATA_PROVIDER"
+        );
+        require!(
+            attestation.timestamp >= oracle_data.timestamp
+                && attestation.timestamp <= env::block_timestamp_ms(),
+            "ERR_INVALID_ATTESTATION_TIMESTAMP"
+        );
+        let message = attestation_message(
+            &oracle_data.id,
+            return_value,
+            attestation.timestamp,
+            evidence,
+        );
+        require!(
+            ed25519_verify(
+                &attestation.signature.0,
+                &message,
+                &attestation.public_key.as_bytes()[1..],
+            ),
+            "ERR_INVALID_SIGNATURE"
+        );
+    }
+}
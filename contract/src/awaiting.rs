@@ -0,0 +1,177 @@
+use crate::callback::{Callback, DeliveryStatus};
+use crate::encoding::ResultEncoding;
+use crate::event::{emit, OracleEventKind};
+use crate::*;
+use near_sdk::json_types::U64;
+use near_sdk::{Gas, PromiseOrValue};
+
+pub use near_oracle_types::{AwaitError, AwaitOutcome};
+
+pub const DEFAULT_AWAIT_METHOD: &str = "on_oracle_result";
+pub const DEFAULT_AWAIT_GAS: Gas = Gas(20_000_000_000_000);
+pub const DEFAULT_AWAIT_TIMEOUT_MS: u64 = 60 * 1000;
+/// Longest an awaiter may wait: one day.
+pub const MAX_AWAIT_TIMEOUT_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Each polling hop is a receipt executed in the next block.
+pub const DEFAULT_MAX_WAIT_BLOCKS: u32 = 15;
+pub const MAX_WAIT_BLOCKS: u32 = 50;
+/// Gas burnt by one `loop_await_return` hop, including scheduling the next one.
+pub const GAS_PER_AWAIT_HOP: Gas = Gas(5_000_000_000_000);
+
+#[near_bindgen]
+impl Contract {
+    /// Queues a request and registers the caller to be resumed through `callback` (by default
+    /// `<predecessor>.on_oracle_result`) as soon as `execute_oracle` writes the result, or with
+    /// `AwaitError::TimedOut` once `expire_oracle` is called after `timeout_ms` (at most
+    /// `MAX_AWAIT_TIMEOUT_MS`).
+    #[payable]
+    pub fn create_oracle_await(
+        &mut self,
+        url: String,
+        data: String,
+        callback: Option<Callback>,
+        timeout_ms: Option<u64>,
+        encoding: Option<ResultEncoding>,
+    ) -> OracleData {
+        require!(
+            env::attached_deposit() >= self.fee_per_call.0,
+            "ERR_NOT_ENOUGH_DEPOSIT"
+        );
+        let timeout_ms = timeout_ms.unwrap_or(DEFAULT_AWAIT_TIMEOUT_MS);
+        require!(timeout_ms <= MAX_AWAIT_TIMEOUT_MS, "ERR_TIMEOUT_TOO_LONG");
+        let callback = callback.unwrap_or_else(|| Callback {
+            contract_id: env::predecessor_account_id(),
+            method: DEFAULT_AWAIT_METHOD.to_string(),
+            gas: DEFAULT_AWAIT_GAS.0.into(),
+        });
+        let mut oracle_data = self.internal_create_oracle(url, data, Some(callback), encoding);
+
+        let init_storage = env::storage_usage();
+        oracle_data.deadline = Some(env::block_timestamp_ms() + timeout_ms);
+        self.queued_data.insert(&oracle_data.id, &oracle_data);
+        self.cal_storage(init_storage, &oracle_data.creator);
+        oracle_data
+    }
+    /// Resumes the awaiter of an unfulfilled request with a timeout error once its deadline
+    /// passed. Anyone may call it.
+    pub fn expire_oracle(&mut self, id: String) -> OracleData {
+        let mut oracle_data = self.queued_data.get(&id).expect("ERR_ORACLE_NOT_FOUND");
+        require!(
+            !oracle_data.executed && oracle_data.error.is_none(),
+            "ERR_ORACLE_ALREADY_RESOLVED"
+        );
+        require!(
+            oracle_data
+                .deadline
+                .is_some_and(|deadline| env::block_timestamp_ms() > deadline),
+            "ERR_DEADLINE_NOT_REACHED"
+        );
+        oracle_data.error = Some(AwaitError::TimedOut);
+        oracle_data.delivery = Some(DeliveryStatus::Pending { attempts: 1 });
+        self.queued_data.insert(&id, &oracle_data);
+        emit(OracleEventKind::OracleExpired, vec![id]);
+        self.internal_deliver(&oracle_data);
+        oracle_data
+    }
+}
+
+/// loop await api return
+#[near_bindgen]
+impl Contract {
+    /// Polls a request once per block for at most `max_wait_blocks` blocks (default 15),
+    /// attaching `gas_budget` (default `estimate_await_gas(max_wait_blocks)`) to the chain.
+    pub fn loop_await_entry(
+        &mut self,
+        id: String,
+        max_wait_blocks: Option<u32>,
+        gas_budget: Option<U64>,
+    ) -> PromiseOrValue<AwaitOutcome> {
+        self.internal_loop_await(id, max_wait_blocks, gas_budget)
+    }
+    /// Same as `create_oracle` followed by `loop_await_entry`.
+    #[payable]
+    pub fn create_oracle_poll(
+        &mut self,
+        url: String,
+        data: String,
+        max_wait_blocks: Option<u32>,
+        gas_budget: Option<U64>,
+    ) -> PromiseOrValue<AwaitOutcome> {
+        require!(
+            env::attached_deposit() >= self.fee_per_call.0,
+            "ERR_NOT_ENOUGH_DEPOSIT"
+        );
+        let oracle_data = self.internal_create_oracle(url, data, None, None);
+        self.internal_loop_await(oracle_data.id, max_wait_blocks, gas_budget)
+    }
+    #[private]
+    pub fn loop_await_return(
+        &self,
+        id: String,
+        iteration: u32,
+        max_wait_blocks: u32,
+    ) -> PromiseOrValue<AwaitOutcome> {
+        if let Some(outcome) = self.internal_await_outcome(&id) {
+            return PromiseOrValue::Value(outcome);
+        }
+        let remaining_gas = env::prepaid_gas() - env::used_gas();
+        if iteration >= max_wait_blocks || remaining_gas < GAS_PER_AWAIT_HOP {
+            return PromiseOrValue::Value(AwaitOutcome::TimedOut {
+                iterations: iteration,
+            });
+        }
+        PromiseOrValue::Promise(
+            Self::ext(env::current_account_id())
+                .with_unused_gas_weight(1)
+                .loop_await_return(id, iteration + 1, max_wait_blocks),
+        )
+    }
+}
+//view
+#[near_bindgen]
+impl Contract {
+    /// Gas to attach to a polling chain so it can run for `max_wait_blocks` blocks, which is
+    /// capped at `MAX_WAIT_BLOCKS`.
+    pub fn estimate_await_gas(max_wait_blocks: u32) -> U64 {
+        let hops = max_wait_blocks.min(MAX_WAIT_BLOCKS) as u64 + 1;
+        U64(GAS_PER_AWAIT_HOP.0 * hops)
+    }
+}
+impl Contract {
+    pub fn internal_await_outcome(&self, id: &String) -> Option<AwaitOutcome> {
+        match self.queued_data.get(id) {
+            None => Some(AwaitOutcome::Failed("ERR_ORACLE_NOT_FOUND".to_string())),
+            Some(oracle_data) if oracle_data.error.is_some() => {
+                Some(AwaitOutcome::Failed("ERR_ORACLE_EXPIRED".to_string()))
+            }
+            Some(oracle_data) if oracle_data.executed => Some(AwaitOutcome::Fulfilled(
+                oracle_data.return_value.unwrap_or_default(),
+            )),
+            Some(_) => None,
+        }
+    }
+    fn internal_loop_await(
+        &self,
+        id: String,
+        max_wait_blocks: Option<u32>,
+        gas_budget: Option<U64>,
+    ) -> PromiseOrValue<AwaitOutcome> {
+        let max_wait_blocks = max_wait_blocks.unwrap_or(DEFAULT_MAX_WAIT_BLOCKS);
+        require!(max_wait_blocks <= MAX_WAIT_BLOCKS, "ERR_WAIT_TOO_LONG");
+        if let Some(outcome) = self.internal_await_outcome(&id) {
+            return PromiseOrValue::Value(outcome);
+        }
+        let gas_budget = gas_budget.unwrap_or_else(|| Self::estimate_await_gas(max_wait_blocks));
+        require!(
+            gas_budget.0 <= (env::prepaid_gas() - env::used_gas()).0,
+            "ERR_NOT_ENOUGH_GAS"
+        );
+        PromiseOrValue::Promise(
+            Self::ext(env::current_account_id())
+                .with_static_gas(Gas(gas_budget.0))
+                .with_unused_gas_weight(0)
+                .loop_await_return(id, 0, max_wait_blocks),
+        )
+    }
+}
@@ -0,0 +1,108 @@
+use crate::*;
+use near_sdk::json_types::U64;
+use near_sdk::{Gas, Promise, PromiseError, PromiseResult};
+
+pub use near_oracle_types::callback::{Callback, DeliveryStatus, MAX_CALLBACK_GAS};
+
+pub const GAS_FOR_RESOLVE_DELIVERY: Gas = Gas(10_000_000_000_000);
+
+/// Outcome of the consumer call a `resolve_*` callback was chained to.
+pub fn consumer_call_result() -> Result<(), PromiseError> {
+    match env::promise_result(0) {
+        PromiseResult::Successful(_) => Ok(()),
+        PromiseResult::NotReady => Err(PromiseError::NotReady),
+        PromiseResult::Failed => Err(PromiseError::Failed),
+    }
+}
+
+/// Status of a delivery that was `delivery` once it resolved with `call_result`.
+pub fn resolved_delivery(
+    delivery: &Option<DeliveryStatus>,
+    call_result: &Result<(), PromiseError>,
+) -> DeliveryStatus {
+    let attempts = match delivery {
+        Some(DeliveryStatus::Pending { attempts }) => *attempts,
+        _ => 1,
+    };
+    match call_result {
+        Ok(()) => DeliveryStatus::Delivered,
+        Err(_) => DeliveryStatus::DeliveryFailed { attempts },
+    }
+}
+
+/// Status of a retry of the failed `delivery`, and `callback` with the retry's gas:
+/// `gas` replaces the registered gas for this attempt only, up to `MAX_CALLBACK_GAS`.
+pub fn retry_delivery(
+    delivery: &Option<DeliveryStatus>,
+    callback: Option<&Callback>,
+    gas: Option<U64>,
+) -> (DeliveryStatus, Callback) {
+    let attempts = match delivery {
+        Some(DeliveryStatus::DeliveryFailed { attempts }) => Some(*attempts),
+        _ => None,
+    }
+    .expect("ERR_NOTHING_TO_REDELIVER");
+    let mut callback = callback.cloned().expect("ERR_NOTHING_TO_REDELIVER");
+    if let Some(gas) = gas {
+        callback.gas = gas;
+        callback.assert_valid();
+    }
+    (
+        DeliveryStatus::Pending {
+            attempts: attempts + 1,
+        },
+        callback,
+    )
+}
+
+impl Contract {
+    /// Pushes the result of a fulfilled request to its callback, if it registered one.
+    pub fn internal_deliver(&self, oracle_data: &OracleData) {
+        if let Some(callback) = &oracle_data.callback {
+            self.internal_call_consumer(
+                callback,
+                oracle_data.encoded_result(),
+                Self::ext_resolve().resolve_delivery(oracle_data.id.clone()),
+            );
+        }
+    }
+    /// Calls `callback` with `args`, then `resolve`, which records the outcome.
+    pub fn internal_call_consumer(&self, callback: &Callback, args: Vec<u8>, resolve: Promise) {
+        Promise::new(callback.contract_id.clone())
+            .function_call(callback.method.clone(), args, 0, Gas(callback.gas.0))
+            .then(resolve);
+    }
+    /// Self call with the gas a `resolve_*` callback needs.
+    pub fn ext_resolve() -> ContractExt {
+        Self::ext(env::current_account_id()).with_static_gas(GAS_FOR_RESOLVE_DELIVERY)
+    }
+}
+#[near_bindgen]
+impl Contract {
+    /// Records the outcome of a callback delivery. The consumer's return value is ignored.
+    #[private]
+    pub fn resolve_delivery(&mut self, id: String) -> bool {
+        let call_result = consumer_call_result();
+        if let Some(mut oracle_data) = self.queued_data.get(&id) {
+            oracle_data.delivery = Some(resolved_delivery(&oracle_data.delivery, &call_result));
+            self.queued_data.insert(&id, &oracle_data);
+        }
+        call_result.is_ok()
+    }
+    /// Retries a failed callback delivery; anyone may call it, attaching gas for the callback.
+    /// `gas` replaces the callback's registered gas for this attempt only, up to
+    /// `MAX_CALLBACK_GAS`, so a callback that ran out of gas can still be delivered.
+    pub fn redeliver(&mut self, id: String, gas: Option<U64>) -> DeliveryStatus {
+        let mut oracle_data = self.queued_data.get(&id).expect("ERR_ORACLE_NOT_FOUND");
+        let (delivery, callback) =
+            retry_delivery(&oracle_data.delivery, oracle_data.callback.as_ref(), gas);
+        oracle_data.delivery = Some(delivery.clone());
+        self.queued_data.insert(&id, &oracle_data);
+        self.internal_call_consumer(
+            &callback,
+            oracle_data.encoded_result(),
+            Self::ext_resolve().resolve_delivery(id),
+        );
+        delivery
+    }
+}
@@ -1,29 +1,17 @@
+use crate::attestation::Attestation;
+use crate::awaiting::{AwaitOutcome, DEFAULT_MAX_WAIT_BLOCKS};
+use crate::callback::{Callback, DeliveryStatus};
+use crate::encoding::ResultEncoding;
+use crate::event::{emit, OracleEventKind};
+use crate::evidence::ResponseEvidence;
 use crate::*;
 use near_sdk::{
     serde_json::{from_str, Value},
-    PromiseOrValue,
+    Gas, PromiseOrValue,
 };
 
-pub trait ExtSelf {
-    fn loop_await_return(&mut self, id: String) -> PromiseOrValue<String>;
-    fn demo_callback(&mut self) -> bool;
-}
+pub use near_oracle_types::{OracleData, Url};
 
-type URL = String;
-const MAX_ITERATIONS: u32 = 15;
-#[derive(
-    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PanicOnDefault, Debug,
-)]
-#[serde(crate = "near_sdk::serde")]
-pub struct OracleData {
-    pub id: String,
-    pub url: URL,
-    pub data: String,
-    pub timestamp: u64,
-    pub executed: bool,
-    pub return_value: Option<String>,
-    pub creator: AccountId,
-}
 //view
 #[near_bindgen]
 impl Contract {
@@ -43,7 +31,7 @@ impl Contract {
     }
     pub fn get_queued_data_by_url(
         &self,
-        url: URL,
+        url: Url,
         from_index: Option<u64>,
         limit: Option<u64>,
     ) -> Vec<OracleData> {
@@ -79,9 +67,19 @@ impl Contract {
     }
 }
 impl Contract {
-    pub fn internal_create_oracle(&mut self, url: URL, data: String) -> OracleData {
+    pub fn internal_create_oracle(
+        &mut self,
+        url: Url,
+        data: String,
+        callback: Option<Callback>,
+        encoding: Option<ResultEncoding>,
+    ) -> OracleData {
         let init_storage = env::storage_usage();
+        if let Some(callback) = &callback {
+            callback.assert_valid();
+        }
         let body_json: Value = from_str(&data).unwrap();
+        let transform = self.internal_request_transform(&body_json);
         //create id hash with timestamp and accountId
 
         //Vec<u8> to String
@@ -101,8 +99,19 @@ impl Contract {
             executed: false,
             return_value: None,
             creator: env::predecessor_account_id(),
+            attestation: None,
+            publisher: None,
+            evidence: None,
+            callback,
+            delivery: None,
+            deadline: None,
+            error: None,
+            executed_at: None,
+            encoding,
+            transform,
         };
         self.queued_data.insert(&id, &oracle_data);
+        emit(OracleEventKind::OracleCreated, vec![id.clone()]);
 
         self.cal_storage(init_storage, &env::predecessor_account_id());
         oracle_data
@@ -110,29 +119,24 @@ impl Contract {
 }
 #[near_bindgen]
 impl Contract {
+    /// `encoding` selects how the result is passed to `callback` and returned by
+    /// `get_encoded_result` (JSON by default).
     #[payable]
-    pub fn create_oracle(&mut self, url: URL, data: String) -> OracleData {
+    pub fn create_oracle(
+        &mut self,
+        url: Url,
+        data: String,
+        callback: Option<Callback>,
+        encoding: Option<ResultEncoding>,
+    ) -> OracleData {
         require!(
             env::attached_deposit() >= self.fee_per_call.0,
             "ERR_NOT_ENOUGH_DEPOSIT"
         );
-        self.internal_create_oracle(url, data)
+        self.internal_create_oracle(url, data, callback, encoding)
     }
     #[payable]
-    pub fn create_oracle_await(&mut self, url: URL, data: String) -> PromiseOrValue<String> {
-        require!(
-            env::attached_deposit() >= self.fee_per_call.0,
-            "ERR_NOT_ENOUGH_DEPOSIT"
-        );
-        let oracle_data = self.internal_create_oracle(url, data);
-        PromiseOrValue::Promise(
-            Self::ext(env::current_account_id())
-                .with_unused_gas_weight(100)
-                .loop_await_return(oracle_data.id, 0),
-        )
-    }
-    #[payable]
-    pub fn demo_power(&mut self) -> PromiseOrValue<String> {
+    pub fn demo_power(&mut self) -> PromiseOrValue<bool> {
         require!(
             env::attached_deposit() >= self.fee_per_call.0,
             "ERR_NOT_ENOUGH_DEPOSIT"
@@ -140,108 +144,179 @@ impl Contract {
         let oracle_data = self.internal_create_oracle(
             "https://api.coingecko.com/api/v3/simple/price?ids=near&vs_currencies=usd".to_string(),
             "{}".to_string(),
+            None,
+            None,
         );
         PromiseOrValue::Promise(
             Self::ext(env::current_account_id())
-                .with_unused_gas_weight(100)
-                .loop_await_return(oracle_data.id, 0)
+                .with_static_gas(Gas(Self::estimate_await_gas(DEFAULT_MAX_WAIT_BLOCKS).0))
+                .with_unused_gas_weight(0)
+                .loop_await_return(oracle_data.id, 0, DEFAULT_MAX_WAIT_BLOCKS)
                 .then(
                     Self::ext(env::current_account_id())
-                        .with_unused_gas_weight(100)
+                        .with_unused_gas_weight(1)
                         .demo_callback(),
                 ),
         )
     }
     //calculate Gas cost of this function
-    pub fn execute_oracle(&mut self, id: String, return_value: String) -> OracleData {
-        /*  let init_storage = env::storage_usage(); */
-        let mut oracle_data = self.queued_data.get(&id).unwrap();
-        oracle_data.executed = true;
-        oracle_data.return_value = Some(return_value);
-        self.queued_data.insert(&oracle_data.id, &oracle_data);
-        /*  self.cal_storage(init_storage, &oracle_data.creator); */
-        oracle_data
+    /// Writes the value of a request. Without an `attestation` the caller must be whitelisted
+    /// (`add_whitelisted`); with one, anyone may relay a value signed by a data provider.
+    pub fn execute_oracle(
+        &mut self,
+        id: String,
+        return_value: String,
+        attestation: Option<Attestation>,
+        evidence: Option<ResponseEvidence>,
+        raw_body: Option<String>,
+    ) -> OracleData {
+        let mut oracle_data = self.queued_data.get(&id).expect("ERR_ORACLE_NOT_FOUND");
+        match &attestation {
+            Some(attestation) => self.assert_valid_attestation(
+                &oracle_data,
+                &return_value,
+                attestation,
+                evidence.as_ref(),
+            ),
+            None => self.assert_whitelisted(),
+        }
+        match &evidence {
+            Some(evidence) => {
+                self.assert_valid_evidence(evidence, &return_value, raw_body.as_deref())
+            }
+            None => require!(raw_body.is_none(), "ERR_BODY_WITHOUT_EVIDENCE"),
+        }
+        oracle_data.attestation = attestation;
+        if let Some(evidence) = evidence {
+            // the creator pays for the evidence, whose proof is bounded by MAX_PROOF_BYTES
+            let init_storage = env::storage_usage();
+            oracle_data.evidence = Some(evidence);
+            self.queued_data.insert(&id, &oracle_data);
+            self.cal_storage(init_storage, &oracle_data.creator);
+        }
+        self.internal_execute_oracle(oracle_data, return_value)
     }
+    /// Writes the values of many requests; the caller must be whitelisted.
     pub fn execute_oracle_batch(&mut self, ids: Vec<String>, return_values: Vec<String>) {
-        /*   let init_storage = env::storage_usage(); */
-        for i in 0..ids.len() {
-            let mut oracle_data = self.queued_data.get(&ids[i]).unwrap();
-            oracle_data.executed = true;
-            oracle_data.return_value = Some(return_values[i].to_string());
-            self.queued_data.insert(&oracle_data.id, &oracle_data);
-            /*    self.cal_storage(init_storage, &oracle_data.creator); */
+        self.assert_whitelisted();
+        require!(ids.len() == return_values.len(), "ERR_LENGTH_MISMATCH");
+        // a request that cannot be executed is skipped so the rest of the batch still goes through
+        for (id, return_value) in ids.iter().zip(return_values) {
+            let oracle_data = match self.queued_data.get(id) {
+                Some(oracle_data) => oracle_data,
+                None => {
+                    env::log_str(&format!("Skipping {}: ERR_ORACLE_NOT_FOUND", id));
+                    continue;
+                }
+            };
+            if let Err(err) = Self::check_executable(&oracle_data, &return_value) {
+                env::log_str(&format!("Skipping {}: {}", id, err));
+                continue;
+            }
+            self.internal_execute_oracle(oracle_data, return_value);
         }
     }
+    /// Only the creator of each request, or the owner, may delete it.
     pub fn delete_oracle_batch(&mut self, ids: Vec<String>) {
         /*  let init_storage = env::storage_usage(); */
-        for i in 0..ids.len() {
-            let oracle_data = self.queued_data.get(&ids[i]).unwrap();
-            self.queued_data.remove(&ids[i]);
+        for id in ids.iter() {
+            let oracle_data = self.queued_data.get(id).expect("ERR_ORACLE_NOT_FOUND");
+            self.assert_creator_or_owner(&oracle_data);
+            self.queued_data.remove(id);
             /*  self.cal_storage(init_storage, &oracle_data.creator); */
         }
+        emit(OracleEventKind::OracleDeleted, ids);
     }
     //calculate Gas cost of this function
+    /// Only the creator of the request, or the owner, may delete it.
     pub fn delete_oracle(&mut self, id: String) -> bool {
         /*   let init_storage = env::storage_usage(); */
 
         if let Some(oracle_data) = self.queued_data.get(&id) {
+            self.assert_creator_or_owner(&oracle_data);
             self.queued_data.remove(&id);
             /*  self.cal_storage(init_storage, &oracle_data.creator); */
+            emit(OracleEventKind::OracleDeleted, vec![id]);
             return true;
         }
         false
     }
 }
-
-/// loop await api return
-#[near_bindgen]
 impl Contract {
-    pub fn loop_await_entry(&mut self, id: String) -> PromiseOrValue<Option<String>> {
-        let data = self.get_queued_data_by_id(id.clone()).unwrap();
-        if data.executed {
-            PromiseOrValue::Value(Some(data.return_value.unwrap()))
-        } else {
-            PromiseOrValue::Promise(
-                Self::ext(env::current_account_id())
-                    .with_unused_gas_weight(100)
-                    .loop_await_return(id.clone(), 0),
-            )
+    pub fn assert_creator_or_owner(&self, oracle_data: &OracleData) {
+        let predecessor = env::predecessor_account_id();
+        require!(
+            oracle_data.creator == predecessor || self.owner_id == predecessor,
+            "ERR_NOT_CREATOR_OR_OWNER"
+        );
+    }
+    /// The reason `return_value` cannot fulfil the request, if any.
+    pub fn check_executable(
+        oracle_data: &OracleData,
+        return_value: &str,
+    ) -> Result<(), &'static str> {
+        if oracle_data.executed {
+            return Err("ERR_ALREADY_EXECUTED");
         }
+        let past_deadline = oracle_data
+            .deadline
+            .is_some_and(|deadline| env::block_timestamp_ms() > deadline);
+        if oracle_data.error.is_some() || past_deadline {
+            return Err("ERR_ORACLE_EXPIRED");
+        }
+        if let Some(ResultEncoding::Borsh { value_type }) = &oracle_data.encoding {
+            if value_type.borsh_encode(return_value).is_none() {
+                return Err("ERR_INVALID_VALUE_FOR_ENCODING");
+            }
+        }
+        Ok(())
     }
-    #[private]
-    pub fn loop_await_return(&self, id: String, iteration: u32) -> PromiseOrValue<Option<String>> {
-        if iteration > MAX_ITERATIONS {
-            return PromiseOrValue::Value(None);
+    pub fn internal_execute_oracle(
+        &mut self,
+        mut oracle_data: OracleData,
+        return_value: String,
+    ) -> OracleData {
+        if let Err(err) = Self::check_executable(&oracle_data, &return_value) {
+            require!(false, err);
         }
-
-        let data = self.get_queued_data_by_id(id.clone()).unwrap();
-        if data.executed {
-            PromiseOrValue::Value(Some(data.return_value.unwrap()))
-        } else {
-            PromiseOrValue::Promise(
-                Self::ext(env::current_account_id())
-                    .with_unused_gas_weight(100)
-                    .loop_await_return(id.clone(), iteration + 1),
-            )
+        /*  let init_storage = env::storage_usage(); */
+        oracle_data.executed = true;
+        oracle_data.return_value = Some(return_value);
+        oracle_data.executed_at = Some(env::block_timestamp_ms());
+        if oracle_data.callback.is_some() {
+            oracle_data.delivery = Some(DeliveryStatus::Pending { attempts: 1 });
         }
+        self.queued_data.insert(&oracle_data.id, &oracle_data);
+        emit(
+            OracleEventKind::OracleExecuted,
+            vec![oracle_data.id.clone()],
+        );
+        /*  self.cal_storage(init_storage, &oracle_data.creator); */
+        self.internal_deliver(&oracle_data);
+        oracle_data
     }
+}
+
+#[near_bindgen]
+impl Contract {
     #[private]
     pub fn demo_callback(
         &mut self,
-        #[callback_result] call_result: Result<String, near_sdk::PromiseError>,
+        #[callback_result] call_result: Result<AwaitOutcome, near_sdk::PromiseError>,
     ) -> bool {
         // Return whether or not the promise succeeded using the method outlined in external.rs
-        if call_result.is_err() {
-            return false;
-        } else {
-            env::log_str(
-                format!(
-                    "You have just connected this contract to the real world by getting the near exchange rate from coingecko within this function call - , {}",
-                    call_result.unwrap()
-                )
-                .as_str(),
-            );
-            return true;
+        match call_result {
+            Ok(AwaitOutcome::Fulfilled(value)) => {
+                env::log_str(
+                    format!(
+                        "You have just connected this contract to the real world by getting the near exchange rate from coingecko within this function call - , {}",
+                        value
+                    )
+                    .as_str(),
+                );
+                true
+            }
+            _ => false,
         }
     }
 }
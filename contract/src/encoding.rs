@@ -0,0 +1,16 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+
+pub use near_oracle_types::{ResultEncoding, ValueType};
+
+//view
+#[near_bindgen]
+impl Contract {
+    /// The payload a request's callback receives, in the request's encoding.
+    pub fn get_encoded_result(&self, id: String) -> Option<Base64VecU8> {
+        self.queued_data
+            .get(&id)
+            .filter(|oracle_data| oracle_data.executed || oracle_data.error.is_some())
+            .map(|oracle_data| oracle_data.encoded_result().into())
+    }
+}
@@ -0,0 +1,8 @@
+use crate::*;
+
+pub use near_oracle_types::event::{OracleEvent, OracleEventKind};
+
+/// Logs a NEP-297 `event` for the requests `ids`.
+pub fn emit(event: OracleEventKind, ids: Vec<String>) {
+    env::log_str(&OracleEvent::new(event, ids).to_log());
+}
@@ -0,0 +1,59 @@
+use crate::*;
+use near_sdk::serde_json::{from_str, Value};
+
+pub use near_oracle_types::ResponseEvidence;
+
+/// Largest `proof` blob stored with a fulfilment.
+pub const MAX_PROOF_BYTES: usize = 4096;
+
+/// Whether `value` can be read out of `body`: a JSON subtree or string leaf of a JSON body,
+/// otherwise a plain substring of the body.
+pub fn value_in_body(body: &str, value: &str) -> bool {
+    match (from_str::<Value>(body), from_str::<Value>(value)) {
+        (Ok(body), Ok(parsed)) => {
+            // "42" may be the number 42 or a string leaf "42"
+            contains_json(&body, &parsed) || contains_json(&body, &Value::String(value.to_string()))
+        }
+        (Ok(body), Err(_)) => contains_json(&body, &Value::String(value.to_string())),
+        _ => body.contains(value),
+    }
+}
+
+fn contains_json(haystack: &Value, needle: &Value) -> bool {
+    if haystack == needle {
+        return true;
+    }
+    match haystack {
+        Value::Array(items) => items.iter().any(|item| contains_json(item, needle)),
+        Value::Object(fields) => fields.values().any(|field| contains_json(field, needle)),
+        _ => false,
+    }
+}
+
+impl Contract {
+    pub fn assert_valid_evidence(
+        &self,
+        evidence: &ResponseEvidence,
+        return_value: &str,
+        raw_body: Option<&str>,
+    ) {
+        require!(
+            evidence
+                .proof
+                .as_ref()
+                .is_none_or(|proof| proof.0.len() <= MAX_PROOF_BYTES),
+            "ERR_PROOF_TOO_LARGE"
+        );
+        let body_sha256 = hex::decode(&evidence.body_sha256)
+            .ok()
+            .filter(|hash| hash.len() == 32)
+            .unwrap_or_else(|| env::panic_str("ERR_INVALID_BODY_HASH"));
+        if let Some(body) = raw_body {
+            require!(
+                env::sha256(body.as_bytes()) == body_sha256,
+                "ERR_BODY_HASH_MISMATCH"
+            );
+            require!(value_in_body(body, return_value), "ERR_VALUE_NOT_IN_BODY");
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::*;
+
+pub use near_oracle_types::feed::{deviation_bps, Feed, FeedSpec, Round};
+
+pub const DEFAULT_HISTORY_SIZE: u32 = 64;
+pub const MAX_HISTORY_SIZE: u32 = 1024;
+
+//owner
+#[near_bindgen]
+impl Contract {
+    /// The feed and its round history are charged to the owner's storage balance.
+    pub fn create_feed(
+        &mut self,
+        id: String,
+        spec: FeedSpec,
+        heartbeat_secs: u64,
+        deviation_threshold_bps: u32,
+        decimals: u8,
+        history_size: Option<u32>,
+    ) -> Feed {
+        self.assert_owner();
+        require!(self.feeds.get(&id).is_none(), "ERR_FEED_EXISTS");
+        require!(heartbeat_secs > 0, "ERR_INVALID_HEARTBEAT");
+        let sponsor = self.owner_id.clone();
+        require!(
+            self.is_registered(sponsor.clone()),
+            "ERR_SPONSOR_NOT_REGISTERED"
+        );
+        let history_size = history_size.unwrap_or(DEFAULT_HISTORY_SIZE);
+        require!(
+            history_size > 0 && history_size <= MAX_HISTORY_SIZE,
+            "ERR_INVALID_HISTORY_SIZE"
+        );
+
+        let init_storage = env::storage_usage();
+        let feed = Feed {
+            id: id.clone(),
+            spec,
+            heartbeat_secs,
+            deviation_threshold_bps,
+            decimals,
+            latest_round: None,
+            history_size,
+            sponsor,
+        };
+        self.feeds.insert(&id, &feed);
+        self.cal_storage(init_storage, &feed.sponsor);
+        feed
+    }
+    pub fn update_feed(
+        &mut self,
+        id: String,
+        spec: Option<FeedSpec>,
+        heartbeat_secs: Option<u64>,
+        deviation_threshold_bps: Option<u32>,
+    ) -> Feed {
+        self.assert_owner();
+        let init_storage = env::storage_usage();
+        let mut feed = self.feeds.get(&id).expect("ERR_FEED_NOT_FOUND");
+        if let Some(spec) = spec {
+            feed.spec = spec;
+        }
+        if let Some(heartbeat_secs) = heartbeat_secs {
+            require!(heartbeat_secs > 0, "ERR_INVALID_HEARTBEAT");
+            feed.heartbeat_secs = heartbeat_secs;
+        }
+        if let Some(deviation_threshold_bps) = deviation_threshold_bps {
+            feed.deviation_threshold_bps = deviation_threshold_bps;
+        }
+        self.feeds.insert(&id, &feed);
+        self.cal_storage(init_storage, &feed.sponsor);
+        feed
+    }
+    pub fn remove_feed(&mut self, id: String) -> bool {
+        self.assert_owner();
+        let init_storage = env::storage_usage();
+        match self.feeds.remove(&id) {
+            Some(feed) => {
+                let rounds = feed.latest_round.as_ref().map_or(0, |round| round.round_id);
+                for round_id in rounds.saturating_sub(feed.history_size as u64) + 1..=rounds {
+                    self.rounds.remove(&feed.round_key(round_id));
+                }
+                self.cal_storage(init_storage, &feed.sponsor);
+                true
+            }
+            None => false,
+        }
+    }
+}
+//operator
+#[near_bindgen]
+impl Contract {
+    /// Records a new round for a feed once its heartbeat elapsed or the answer moved by at
+    /// least the deviation threshold, overwriting the oldest kept round when history is full.
+    pub fn submit_round(&mut self, feed_id: String, answer: U128) -> Round {
+        self.assert_whitelisted();
+        let init_storage = env::storage_usage();
+        let mut feed = self.feeds.get(&feed_id).expect("ERR_FEED_NOT_FOUND");
+        let now = env::block_timestamp_ms();
+        require!(feed.is_round_due(answer.0, now), "ERR_ROUND_NOT_DUE");
+
+        let round = Round {
+            round_id: feed.latest_round.as_ref().map_or(0, |round| round.round_id) + 1,
+            answer,
+            timestamp: now,
+            operator: env::predecessor_account_id(),
+        };
+        self.rounds.insert(&feed.round_key(round.round_id), &round);
+        feed.latest_round = Some(round.clone());
+        self.feeds.insert(&feed_id, &feed);
+        self.cal_storage(init_storage, &feed.sponsor);
+        round
+    }
+}
+//view
+#[near_bindgen]
+impl Contract {
+    pub fn get_feed(&self, feed_id: String) -> Option<Feed> {
+        self.feeds.get(&feed_id)
+    }
+    pub fn get_feeds(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<Feed> {
+        self.feeds
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(_, feed)| feed)
+            .collect()
+    }
+    /// Returns `None` for rounds that were already overwritten in the history ring buffer.
+    pub fn get_round(&self, feed_id: String, round_id: u64) -> Option<Round> {
+        let feed = self.feeds.get(&feed_id)?;
+        self.rounds
+            .get(&feed.round_key(round_id))
+            .filter(|round| round.round_id == round_id)
+    }
+}
@@ -0,0 +1,54 @@
+use crate::*;
+
+pub use near_oracle_types::freshness::{DataRef, FreshValue};
+
+impl Contract {
+    pub fn internal_fresh(
+        &self,
+        target: &DataRef,
+        max_age_secs: u64,
+    ) -> Result<FreshValue, &'static str> {
+        let now = env::block_timestamp_ms();
+        let max_age_ms = max_age_secs.saturating_mul(1000);
+        match target {
+            DataRef::Request(id) => {
+                let oracle_data = self.queued_data.get(id).ok_or("ERR_ORACLE_NOT_FOUND")?;
+                let (value, updated_at) = oracle_data
+                    .return_value
+                    .zip(oracle_data.executed_at)
+                    .ok_or("ERR_NO_DATA")?;
+                if now.saturating_sub(updated_at) > max_age_ms {
+                    return Err("ERR_STALE_DATA");
+                }
+                Ok(FreshValue { value, updated_at })
+            }
+            DataRef::Feed(feed_id) => {
+                let feed = self.feeds.get(feed_id).ok_or("ERR_FEED_NOT_FOUND")?;
+                let round = feed.latest_round.ok_or("ERR_NO_DATA")?;
+                let age = now.saturating_sub(round.timestamp);
+                if age > max_age_ms || age > feed.heartbeat_secs.saturating_mul(1000) {
+                    return Err("ERR_STALE_DATA");
+                }
+                Ok(FreshValue {
+                    value: round.answer.0.to_string(),
+                    updated_at: round.timestamp,
+                })
+            }
+        }
+    }
+}
+//view
+#[near_bindgen]
+impl Contract {
+    /// The value of `target` if it was written within `max_age_secs` (and, for feeds, within
+    /// the feed heartbeat), otherwise `None`.
+    pub fn get_fresh(&self, target: DataRef, max_age_secs: u64) -> Option<FreshValue> {
+        self.internal_fresh(&target, max_age_secs).ok()
+    }
+    /// Like `get_fresh` but panics with `ERR_STALE_DATA` (or why there is no value), so a
+    /// consumer's cross-contract call fails instead of acting on stale data.
+    pub fn require_fresh(&self, target: DataRef, max_age_secs: u64) -> FreshValue {
+        self.internal_fresh(&target, max_age_secs)
+            .unwrap_or_else(|err| env::panic_str(err))
+    }
+}
@@ -0,0 +1,62 @@
+use crate::feed::{Feed, Round};
+use crate::*;
+
+#[allow(clippy::manual_div_ceil, clippy::assign_op_pattern)]
+mod u256 {
+    uint::construct_uint! {
+        /// 256-bit accumulator so `answer * duration` sums cannot overflow.
+        pub struct U256(4);
+    }
+}
+pub use u256::U256;
+
+impl Contract {
+    /// Kept rounds of a feed, newest first.
+    pub fn internal_rounds_desc(&self, feed: Feed) -> impl Iterator<Item = Round> + '_ {
+        let latest = feed.latest_round.as_ref().map_or(0, |round| round.round_id);
+        let oldest = latest.saturating_sub(feed.history_size as u64) + 1;
+        (oldest..=latest)
+            .rev()
+            .filter_map(move |round_id| self.rounds.get(&feed.round_key(round_id)))
+    }
+}
+//view
+#[near_bindgen]
+impl Contract {
+    /// Time-weighted average answer over the last `window_secs`, each round weighted by how long
+    /// it was the latest. Only the part of the window covered by kept history is averaged.
+    pub fn get_twap(&self, feed_id: String, window_secs: u64) -> Option<U128> {
+        require!(window_secs > 0, "ERR_INVALID_WINDOW");
+        let feed = self.feeds.get(&feed_id)?;
+        let now = env::block_timestamp_ms();
+        let start = now.saturating_sub(window_secs.saturating_mul(1000));
+
+        let mut weighted_sum = U256::zero();
+        let mut total_duration = 0u64;
+        let mut end = now;
+        let mut latest_answer = None;
+        for round in self.internal_rounds_desc(feed) {
+            latest_answer.get_or_insert(round.answer);
+            let from = round.timestamp.max(start);
+            if end > from {
+                weighted_sum += U256::from(round.answer.0) * U256::from(end - from);
+                total_duration += end - from;
+            }
+            if round.timestamp <= start {
+                break;
+            }
+            end = round.timestamp;
+        }
+        if total_duration == 0 {
+            // only a round recorded in this very block
+            return latest_answer;
+        }
+        Some(U128((weighted_sum / U256::from(total_duration)).as_u128()))
+    }
+    /// The round that was the latest at `timestamp` (ms), if still kept in history.
+    pub fn get_round_at(&self, feed_id: String, timestamp: u64) -> Option<Round> {
+        let feed = self.feeds.get(&feed_id)?;
+        self.internal_rounds_desc(feed)
+            .find(|round| round.timestamp <= timestamp)
+    }
+}
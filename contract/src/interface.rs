@@ -0,0 +1,29 @@
+use crate::callback::Callback;
+use crate::feed::Round;
+use crate::*;
+
+pub use near_oracle_types::interface::{
+    ext_oracle, OracleV1, ORACLE_INTERFACE, ORACLE_INTERFACE_VERSION,
+};
+pub use near_oracle_types::OracleResult;
+
+/// Interfaces this contract implements, as `(name, version)`.
+pub const SUPPORTED_INTERFACES: &[(&str, u32)] =
+    &[(ORACLE_INTERFACE, ORACLE_INTERFACE_VERSION), ("nep145", 1)];
+
+#[near_bindgen]
+impl OracleV1 for Contract {
+    #[payable]
+    fn request(&mut self, url: String, data: String, callback: Option<Callback>) -> OracleData {
+        self.create_oracle(url, data, callback, None)
+    }
+    fn get_result(&self, id: String) -> Option<OracleResult> {
+        self.queued_data.get(&id).map(OracleResult::from)
+    }
+    fn get_latest(&self, feed_id: String) -> Option<Round> {
+        self.feeds.get(&feed_id).and_then(|feed| feed.latest_round)
+    }
+    fn supports_interface(&self, name: String, version: u32) -> bool {
+        SUPPORTED_INTERFACES.contains(&(name.as_str(), version))
+    }
+}
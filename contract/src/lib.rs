@@ -8,20 +8,35 @@
 
 use data::OracleData;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LookupMap, LookupSet, UnorderedMap};
+use near_sdk::collections::{LookupMap, LookupSet, UnorderedMap, UnorderedSet};
 
 use near_sdk::json_types::U128;
-use near_sdk::serde::{Deserialize, Serialize};
 
 use near_sdk::{
-    env, near_bindgen, require, AccountId, Balance, BorshStorageKey, PanicOnDefault, StorageUsage,
-    ONE_NEAR,
+    env, near_bindgen, require, AccountId, BorshStorageKey, PanicOnDefault, PublicKey,
+    StorageUsage, ONE_NEAR,
 };
 use storage::StorageBalance;
 
 // Define the default message
-mod data;
-mod storage;
+pub mod attestation;
+pub mod awaiting;
+pub mod callback;
+pub mod data;
+pub mod encoding;
+pub mod event;
+pub mod evidence;
+pub mod feed;
+pub mod freshness;
+pub mod history;
+pub mod interface;
+pub mod migrate;
+pub mod randomness;
+pub mod report;
+pub mod storage;
+pub mod subscription;
+pub mod transform;
+pub mod vrf;
 // Define the contract structure
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
@@ -32,6 +47,16 @@ pub struct Contract {
     pub account_storage_usage: StorageUsage,
     pub whitelisted: LookupSet<AccountId>,
     pub fee_per_call: U128,
+    pub data_providers: UnorderedSet<PublicKey>,
+    pub publishers: UnorderedSet<report::EthAddress>,
+    pub max_report_age_ms: u64,
+    pub feeds: UnorderedMap<String, feed::Feed>,
+    pub rounds: LookupMap<(String, u64), feed::Round>,
+    pub subscriptions: UnorderedMap<String, subscription::Subscription>,
+    pub subscription_nonce: u64,
+    pub randomness_requests: UnorderedMap<String, randomness::RandomnessRequest>,
+    pub randomness_nonce: u64,
+    pub transforms: LookupMap<String, Vec<u8>>,
 }
 
 // Define the default, which automatically initializes the contract
@@ -40,12 +65,20 @@ enum StorageKey {
     OracleData,
     Whitelisted,
     Accounts,
+    DataProviders,
+    Publishers,
+    Feeds,
+    Rounds,
+    Subscriptions,
+    RandomnessRequests,
+    Transforms,
 }
 // Implement the contract structure
 #[near_bindgen]
 impl Contract {
     #[init]
     pub fn new(owner_id: AccountId) -> Self {
+        migrate::write_state_version();
         Self {
             owner_id,
             queued_data: UnorderedMap::new(StorageKey::OracleData.try_to_vec().unwrap()),
@@ -53,6 +86,18 @@ impl Contract {
             accounts: LookupMap::new(StorageKey::Accounts.try_to_vec().unwrap()),
             account_storage_usage: 0,
             fee_per_call: U128(ONE_NEAR / 100),
+            data_providers: UnorderedSet::new(StorageKey::DataProviders.try_to_vec().unwrap()),
+            publishers: UnorderedSet::new(StorageKey::Publishers.try_to_vec().unwrap()),
+            max_report_age_ms: report::DEFAULT_MAX_REPORT_AGE_MS,
+            feeds: UnorderedMap::new(StorageKey::Feeds.try_to_vec().unwrap()),
+            rounds: LookupMap::new(StorageKey::Rounds.try_to_vec().unwrap()),
+            subscriptions: UnorderedMap::new(StorageKey::Subscriptions.try_to_vec().unwrap()),
+            subscription_nonce: 0,
+            randomness_requests: UnorderedMap::new(
+                StorageKey::RandomnessRequests.try_to_vec().unwrap(),
+            ),
+            randomness_nonce: 0,
+            transforms: LookupMap::new(StorageKey::Transforms.try_to_vec().unwrap()),
         }
     }
     pub fn assert_owner(&self) {
@@ -61,6 +106,24 @@ impl Contract {
             "ERR_ONLY_OWNER_ACCESS"
         );
     }
+    pub fn assert_whitelisted(&self) {
+        let predecessor = env::predecessor_account_id();
+        require!(
+            self.owner_id == predecessor || self.whitelisted.contains(&predecessor),
+            "ERR_NOT_WHITELISTED"
+        );
+    }
+    pub fn add_whitelisted(&mut self, account_id: AccountId) -> bool {
+        self.assert_owner();
+        self.whitelisted.insert(&account_id)
+    }
+    pub fn remove_whitelisted(&mut self, account_id: AccountId) -> bool {
+        self.assert_owner();
+        self.whitelisted.remove(&account_id)
+    }
+    pub fn is_whitelisted(&self, account_id: AccountId) -> bool {
+        self.whitelisted.contains(&account_id)
+    }
 }
 
 /*
@@ -76,6 +139,7 @@ mod tests {
         testing_env, ONE_NEAR,
     };
 
+    use crate::interface::OracleV1;
     use crate::storage::StorageManagement;
 
     use super::*;
@@ -99,9 +163,1478 @@ mod tests {
             "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies=usd"
                 .to_string(),
             json!({"id": "bitcoin", "symbol": "btc", "name": "Bitcoin"}).to_string(),
+            None,
+            None,
         );
         let json: Value = serde_json::from_str(&res.data).unwrap();
         assert!(json["id"].as_str().unwrap() == "bitcoin");
         // this test did not call set_greeting so should return the default "Hello" greeting
     }
+
+    fn provider_keypair() -> (ed25519_dalek::Keypair, PublicKey) {
+        use ed25519_dalek::{Keypair, PublicKey as DalekPublicKey, SecretKey};
+        let secret = SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public = DalekPublicKey::from(&secret);
+        let mut key_bytes = vec![0u8];
+        key_bytes.extend_from_slice(public.as_bytes());
+        (Keypair { secret, public }, key_bytes.try_into().unwrap())
+    }
+
+    fn setup_oracle() -> (Contract, OracleData) {
+        let context = VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(ONE_NEAR / 10)
+            .block_timestamp(1_000_000_000)
+            .build();
+        testing_env!(context);
+        let mut contract = Contract::new(accounts(0));
+        contract.storage_deposit(Some(accounts(0)), None);
+        let oracle_data = contract.create_oracle(
+            "https://example.com".to_string(),
+            "{}".to_string(),
+            None,
+            None,
+        );
+        (contract, oracle_data)
+    }
+
+    #[test]
+    fn execute_oracle_with_attestation() {
+        use ed25519_dalek::Signer;
+
+        let (mut contract, oracle_data) = setup_oracle();
+        let (keypair, public_key) = provider_keypair();
+        contract.add_data_provider(public_key.clone());
+
+        let timestamp = oracle_data.timestamp;
+        let message = attestation::attestation_message(&oracle_data.id, "42", timestamp, None);
+        let attestation = attestation::Attestation {
+            public_key,
+            timestamp,
+            signature: keypair.sign(&message).to_bytes().to_vec().into(),
+        };
+        // anyone may relay an attested result
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(2_000_000_000)
+            .build());
+        let executed = contract.execute_oracle(
+            oracle_data.id,
+            "42".to_string(),
+            Some(attestation),
+            None,
+            None,
+        );
+        assert!(executed.executed);
+        assert_eq!(executed.return_value, Some("42".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_SIGNATURE")]
+    fn execute_oracle_rejects_tampered_value() {
+        use ed25519_dalek::Signer;
+
+        let (mut contract, oracle_data) = setup_oracle();
+        let (keypair, public_key) = provider_keypair();
+        contract.add_data_provider(public_key.clone());
+
+        let timestamp = oracle_data.timestamp;
+        let message = attestation::attestation_message(&oracle_data.id, "42", timestamp, None);
+        let attestation = attestation::Attestation {
+            public_key,
+            timestamp,
+            signature: keypair.sign(&message).to_bytes().to_vec().into(),
+        };
+        contract.execute_oracle(
+            oracle_data.id,
+            "43".to_string(),
+            Some(attestation),
+            None,
+            None,
+        );
+    }
+
+    fn price_evidence(body: &str) -> evidence::ResponseEvidence {
+        evidence::ResponseEvidence {
+            body_sha256: hex::encode(env::sha256(body.as_bytes())),
+            status_code: 200,
+            response_timestamp: 1_000,
+            proof: None,
+        }
+    }
+
+    #[test]
+    fn execute_oracle_with_evidence() {
+        let (mut contract, oracle_data) = setup_oracle();
+        let body = json!({"near": {"usd": 3.21}}).to_string();
+        let executed = contract.execute_oracle(
+            oracle_data.id,
+            "3.21".to_string(),
+            None,
+            Some(price_evidence(&body)),
+            Some(body),
+        );
+        assert_eq!(executed.evidence.unwrap().status_code, 200);
+    }
+
+    #[test]
+    fn execute_oracle_with_evidence_of_string_leaf() {
+        let (mut contract, oracle_data) = setup_oracle();
+        let body = json!({"near": {"usd": "3.21"}}).to_string();
+        let executed = contract.execute_oracle(
+            oracle_data.id,
+            "3.21".to_string(),
+            None,
+            Some(price_evidence(&body)),
+            Some(body),
+        );
+        assert_eq!(executed.return_value, Some("3.21".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_VALUE_NOT_IN_BODY")]
+    fn execute_oracle_rejects_value_missing_from_body() {
+        let (mut contract, oracle_data) = setup_oracle();
+        let body = json!({"near": {"usd": 3.21}}).to_string();
+        contract.execute_oracle(
+            oracle_data.id,
+            "4.56".to_string(),
+            None,
+            Some(price_evidence(&body)),
+            Some(body),
+        );
+    }
+
+    /// Attestation by the registered provider over `value` and `evidence`.
+    fn attest(
+        contract: &mut Contract,
+        oracle_data: &OracleData,
+        value: &str,
+        evidence: Option<&evidence::ResponseEvidence>,
+    ) -> attestation::Attestation {
+        use ed25519_dalek::Signer;
+
+        let (keypair, public_key) = provider_keypair();
+        contract.add_data_provider(public_key.clone());
+        let timestamp = oracle_data.timestamp;
+        let message = attestation::attestation_message(&oracle_data.id, value, timestamp, evidence);
+        attestation::Attestation {
+            public_key,
+            timestamp,
+            signature: keypair.sign(&message).to_bytes().to_vec().into(),
+        }
+    }
+
+    #[test]
+    fn relayed_evidence_is_signed_and_charged_to_creator() {
+        let (mut contract, oracle_data) = setup_oracle();
+        let evidence = evidence::ResponseEvidence {
+            proof: Some(vec![1u8; evidence::MAX_PROOF_BYTES].into()),
+            ..price_evidence("{}")
+        };
+        let attestation = attest(&mut contract, &oracle_data, "42", Some(&evidence));
+        let storage_before = contract.storage_balance_of(accounts(0)).unwrap();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(2_000_000_000)
+            .build());
+        let executed = contract.execute_oracle(
+            oracle_data.id,
+            "42".to_string(),
+            Some(attestation),
+            Some(evidence),
+            None,
+        );
+        assert!(executed.evidence.is_some());
+        let storage_after = contract.storage_balance_of(accounts(0)).unwrap();
+        assert!(
+            storage_before.available.0 - storage_after.available.0
+                >= evidence::MAX_PROOF_BYTES as u128 * env::storage_byte_cost()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_SIGNATURE")]
+    fn relayer_cannot_attach_unsigned_evidence() {
+        let (mut contract, oracle_data) = setup_oracle();
+        let attestation = attest(&mut contract, &oracle_data, "42", None);
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(2_000_000_000)
+            .build());
+        contract.execute_oracle(
+            oracle_data.id,
+            "42".to_string(),
+            Some(attestation),
+            Some(price_evidence("{}")),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROOF_TOO_LARGE")]
+    fn execute_oracle_rejects_oversized_proof() {
+        let (mut contract, oracle_data) = setup_oracle();
+        contract.execute_oracle(
+            oracle_data.id,
+            "42".to_string(),
+            None,
+            Some(evidence::ResponseEvidence {
+                proof: Some(vec![1u8; evidence::MAX_PROOF_BYTES + 1].into()),
+                ..price_evidence("{}")
+            }),
+            None,
+        );
+    }
+
+    #[test]
+    fn execute_oracle_delivers_to_callback() {
+        use near_sdk::mock::VmAction;
+        use near_sdk::test_utils::get_created_receipts;
+        use near_sdk::{PromiseResult, RuntimeFeesConfig, VMConfig};
+
+        let (mut contract, _) = setup_oracle();
+        let oracle_data = contract.create_oracle(
+            "https://example.com/callback".to_string(),
+            "{}".to_string(),
+            Some(callback::Callback {
+                contract_id: accounts(2),
+                method: "on_oracle_result".to_string(),
+                gas: 20_000_000_000_000.into(),
+            }),
+            None,
+        );
+        contract.execute_oracle(oracle_data.id.clone(), "42".to_string(), None, None, None);
+
+        let receipt = get_created_receipts()
+            .into_iter()
+            .find(|receipt| receipt.receiver_id == accounts(2))
+            .unwrap();
+        match &receipt.actions[0] {
+            VmAction::FunctionCall {
+                function_name,
+                args,
+                ..
+            } => {
+                assert_eq!(function_name, "on_oracle_result");
+                let args: Value = serde_json::from_slice(args).unwrap();
+                assert_eq!(args, json!({"id": oracle_data.id, "value": "42"}));
+            }
+            action => panic!("unexpected action {:?}", action),
+        }
+
+        testing_env!(
+            VMContextBuilder::new()
+                .predecessor_account_id(accounts(0))
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        assert!(!contract.resolve_delivery(oracle_data.id.clone()));
+        assert_eq!(
+            contract
+                .get_queued_data_by_id(oracle_data.id.clone())
+                .unwrap()
+                .delivery,
+            Some(callback::DeliveryStatus::DeliveryFailed { attempts: 1 })
+        );
+
+        // anyone can retry a failed delivery
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .build());
+        assert_eq!(
+            contract.redeliver(oracle_data.id.clone(), None),
+            callback::DeliveryStatus::Pending { attempts: 2 }
+        );
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(2)));
+
+        // a retry may raise the callback's gas
+        testing_env!(
+            VMContextBuilder::new()
+                .predecessor_account_id(accounts(0))
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.resolve_delivery(oracle_data.id.clone());
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.redeliver(oracle_data.id, Some(50_000_000_000_000.into()));
+        let receipt = get_created_receipts()
+            .into_iter()
+            .find(|receipt| receipt.receiver_id == accounts(2))
+            .unwrap();
+        match &receipt.actions[0] {
+            VmAction::FunctionCall { gas, .. } => assert_eq!(gas.0, 50_000_000_000_000),
+            action => panic!("unexpected action {:?}", action),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CALLBACK_GAS_TOO_HIGH")]
+    fn redeliver_caps_gas_override() {
+        use near_sdk::{PromiseResult, RuntimeFeesConfig, VMConfig};
+
+        let (mut contract, _) = setup_oracle();
+        let oracle_data = contract.create_oracle(
+            "https://example.com/callback".to_string(),
+            "{}".to_string(),
+            Some(callback::Callback {
+                contract_id: accounts(2),
+                method: "on_oracle_result".to_string(),
+                gas: 20_000_000_000_000.into(),
+            }),
+            None,
+        );
+        contract.execute_oracle(oracle_data.id.clone(), "42".to_string(), None, None, None);
+        testing_env!(
+            VMContextBuilder::new()
+                .predecessor_account_id(accounts(0))
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.resolve_delivery(oracle_data.id.clone());
+        contract.redeliver(
+            oracle_data.id,
+            Some((callback::MAX_CALLBACK_GAS.0 + 1).into()),
+        );
+    }
+
+    #[test]
+    fn borsh_encoded_result_is_delivered_and_viewable() {
+        let (mut contract, _) = setup_oracle();
+        let oracle_data = contract.create_oracle(
+            "https://example.com/borsh".to_string(),
+            "{}".to_string(),
+            Some(callback::Callback {
+                contract_id: accounts(2),
+                method: "on_oracle_result".to_string(),
+                gas: 20_000_000_000_000.into(),
+            }),
+            Some(encoding::ResultEncoding::Borsh {
+                value_type: encoding::ValueType::U128,
+            }),
+        );
+        assert!(contract
+            .get_encoded_result(oracle_data.id.clone())
+            .is_none());
+        contract.execute_oracle(oracle_data.id.clone(), "42".to_string(), None, None, None);
+
+        let expected = (
+            oracle_data.id.clone(),
+            Some(42u128),
+            None::<awaiting::AwaitError>,
+        )
+            .try_to_vec()
+            .unwrap();
+        assert_eq!(
+            contract.get_encoded_result(oracle_data.id).unwrap().0,
+            expected
+        );
+        let delivered = near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .find(|receipt| receipt.receiver_id == accounts(2))
+            .unwrap();
+        assert!(matches!(
+            &delivered.actions[0],
+            near_sdk::mock::VmAction::FunctionCall { args, .. } if args == &expected
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_VALUE_FOR_ENCODING")]
+    fn borsh_encoding_rejects_value_of_wrong_type() {
+        let (mut contract, _) = setup_oracle();
+        let oracle_data = contract.create_oracle(
+            "https://example.com/borsh".to_string(),
+            "{}".to_string(),
+            None,
+            Some(encoding::ResultEncoding::Borsh {
+                value_type: encoding::ValueType::U64,
+            }),
+        );
+        contract.execute_oracle(oracle_data.id, "4.2".to_string(), None, None, None);
+    }
+
+    #[test]
+    fn requests_record_registered_transform() {
+        let (mut contract, _) = setup_oracle();
+        let module = b"\0asm\x01\0\0\0".to_vec();
+        let hash = contract.register_transform(module.clone().into());
+        assert_eq!(hash, hex::encode(env::sha256(&module)));
+        assert_eq!(contract.register_transform(module.clone().into()), hash);
+        assert_eq!(
+            Vec::<u8>::from(contract.get_transform(hash.clone()).unwrap()),
+            module
+        );
+
+        let oracle_data = contract.create_oracle(
+            "https://example.com/raw".to_string(),
+            json!({ "transform": hash }).to_string(),
+            None,
+            None,
+        );
+        assert_eq!(oracle_data.transform, Some(hash));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TRANSFORM_NOT_FOUND")]
+    fn requests_reject_unknown_transform() {
+        let (mut contract, _) = setup_oracle();
+        contract.create_oracle(
+            "https://example.com/raw".to_string(),
+            json!({ "transform": "00" }).to_string(),
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    fn queue_changes_emit_events() {
+        use near_sdk::test_utils::get_logs;
+
+        let (mut contract, oracle_data) = setup_oracle();
+        let events = || -> Vec<event::OracleEvent> {
+            get_logs()
+                .iter()
+                .filter_map(|log| event::OracleEvent::from_log(log))
+                .collect()
+        };
+        assert_eq!(
+            events(),
+            vec![event::OracleEvent::new(
+                event::OracleEventKind::OracleCreated,
+                vec![oracle_data.id.clone()]
+            )]
+        );
+
+        contract.execute_oracle_batch(vec![oracle_data.id.clone()], vec!["1".to_string()]);
+        contract.delete_oracle_batch(vec![oracle_data.id.clone()]);
+        let kinds: Vec<_> = events().iter().map(|event| event.event).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                event::OracleEventKind::OracleCreated,
+                event::OracleEventKind::OracleExecuted,
+                event::OracleEventKind::OracleDeleted,
+            ]
+        );
+        assert!(events()
+            .iter()
+            .all(|event| event.ids().eq([oracle_data.id.as_str()])));
+    }
+
+    fn callback_args(receiver_id: &AccountId) -> Value {
+        use near_sdk::mock::VmAction;
+        use near_sdk::test_utils::get_created_receipts;
+
+        get_created_receipts()
+            .into_iter()
+            .filter(|receipt| &receipt.receiver_id == receiver_id)
+            .find_map(|receipt| match &receipt.actions[0] {
+                VmAction::FunctionCall { args, .. } => Some(serde_json::from_slice(args).unwrap()),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn create_oracle_await_resumes_caller_on_execute() {
+        let (mut contract, _) = setup_oracle();
+        let oracle_data = contract.create_oracle_await(
+            "https://example.com/await".to_string(),
+            "{}".to_string(),
+            None,
+            None,
+            None,
+        );
+        contract.execute_oracle(oracle_data.id.clone(), "42".to_string(), None, None, None);
+        assert_eq!(
+            callback_args(&accounts(0)),
+            json!({"id": oracle_data.id, "value": "42"})
+        );
+    }
+
+    #[test]
+    fn create_oracle_await_accepts_longest_timeout() {
+        let (mut contract, _) = setup_oracle();
+        let oracle_data = contract.create_oracle_await(
+            "https://example.com/await".to_string(),
+            "{}".to_string(),
+            None,
+            Some(awaiting::MAX_AWAIT_TIMEOUT_MS),
+            None,
+        );
+        assert_eq!(
+            oracle_data.deadline,
+            Some(oracle_data.timestamp + awaiting::MAX_AWAIT_TIMEOUT_MS)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TIMEOUT_TOO_LONG")]
+    fn create_oracle_await_rejects_unbounded_timeout() {
+        let (mut contract, _) = setup_oracle();
+        contract.create_oracle_await(
+            "https://example.com/await".to_string(),
+            "{}".to_string(),
+            None,
+            Some(u64::MAX),
+            None,
+        );
+    }
+
+    #[test]
+    fn expire_oracle_resumes_caller_with_timeout() {
+        let (mut contract, _) = setup_oracle();
+        let oracle_data = contract.create_oracle_await(
+            "https://example.com/await".to_string(),
+            "{}".to_string(),
+            None,
+            Some(1_000),
+            None,
+        );
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .block_timestamp((oracle_data.timestamp + 1_001) * 1_000_000)
+            .build());
+        contract.expire_oracle(oracle_data.id.clone());
+        assert_eq!(
+            callback_args(&accounts(0)),
+            json!({"id": oracle_data.id, "value": null, "error": "TimedOut"})
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ORACLE_EXPIRED")]
+    fn execute_oracle_rejects_result_after_deadline() {
+        let (mut contract, _) = setup_oracle();
+        let oracle_data = contract.create_oracle_await(
+            "https://example.com/await".to_string(),
+            "{}".to_string(),
+            None,
+            Some(1_000),
+            None,
+        );
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .block_timestamp((oracle_data.timestamp + 1_001) * 1_000_000)
+            .build());
+        contract.execute_oracle(oracle_data.id, "42".to_string(), None, None, None);
+    }
+
+    #[test]
+    fn loop_await_reports_outcomes_without_panicking() {
+        use crate::awaiting::AwaitOutcome;
+        use near_sdk::PromiseOrValue;
+
+        let (mut contract, oracle_data) = setup_oracle();
+        match contract.loop_await_entry("unknown".to_string(), None, None) {
+            PromiseOrValue::Value(outcome) => assert_eq!(
+                outcome,
+                AwaitOutcome::Failed("ERR_ORACLE_NOT_FOUND".to_string())
+            ),
+            PromiseOrValue::Promise(_) => panic!("unknown ids must not be polled"),
+        }
+        match contract.loop_await_return(oracle_data.id.clone(), 3, 3) {
+            PromiseOrValue::Value(outcome) => {
+                assert_eq!(outcome, AwaitOutcome::TimedOut { iterations: 3 })
+            }
+            PromiseOrValue::Promise(_) => panic!("wait budget exhausted"),
+        }
+        contract.execute_oracle(oracle_data.id.clone(), "42".to_string(), None, None, None);
+        match contract.loop_await_return(oracle_data.id, 0, 3) {
+            PromiseOrValue::Value(outcome) => {
+                assert_eq!(outcome, AwaitOutcome::Fulfilled("42".to_string()))
+            }
+            PromiseOrValue::Promise(_) => panic!("request already fulfilled"),
+        }
+        assert_eq!(
+            Contract::estimate_await_gas(3).0,
+            4 * awaiting::GAS_PER_AWAIT_HOP.0
+        );
+    }
+
+    #[test]
+    fn await_gas_estimate_is_capped() {
+        assert_eq!(
+            Contract::estimate_await_gas(u32::MAX),
+            Contract::estimate_await_gas(awaiting::MAX_WAIT_BLOCKS)
+        );
+    }
+
+    #[test]
+    fn oracle_interface_reports_results_and_versions() {
+        let (mut contract, oracle_data) = setup_oracle();
+        assert!(contract.supports_interface("oracle".to_string(), 1));
+        assert!(!contract.supports_interface("oracle".to_string(), 2));
+        assert!(contract.supports_interface("nep145".to_string(), 1));
+
+        let result = contract.get_result(oracle_data.id.clone()).unwrap();
+        assert!(!result.executed && result.value.is_none());
+        contract.execute_oracle(oracle_data.id.clone(), "42".to_string(), None, None, None);
+        let result = contract.get_result(oracle_data.id).unwrap();
+        assert_eq!(result.value, Some("42".to_string()));
+        assert!(contract.get_result("missing".to_string()).is_none());
+    }
+
+    fn setup_feed() -> Contract {
+        let (mut contract, _) = setup_oracle();
+        contract.create_feed(
+            "NEAR/USD".to_string(),
+            feed::FeedSpec {
+                url: "https://api.coingecko.com/api/v3/simple/price?ids=near&vs_currencies=usd"
+                    .to_string(),
+                data: "{}".to_string(),
+            },
+            60,
+            50,
+            6,
+            Some(2),
+        );
+        contract
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ONLY_OWNER_ACCESS")]
+    fn create_feed_requires_owner() {
+        let (mut contract, _) = setup_oracle();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.create_feed(
+            "NEAR/USD".to_string(),
+            feed::FeedSpec {
+                url: "https://example.com/near".to_string(),
+                data: "{}".to_string(),
+            },
+            60,
+            50,
+            6,
+            None,
+        );
+    }
+
+    fn at_time(timestamp_ms: u64) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .block_timestamp(timestamp_ms * 1_000_000)
+            .build());
+    }
+
+    #[test]
+    fn feed_rounds_follow_heartbeat_and_deviation() {
+        let mut contract = setup_feed();
+        at_time(10_000);
+        contract.submit_round("NEAR/USD".to_string(), U128(2_000_000));
+        // a 1% move beats the 0.5% deviation threshold before the heartbeat
+        at_time(11_000);
+        let round = contract.submit_round("NEAR/USD".to_string(), U128(2_020_000));
+        assert_eq!(round.round_id, 2);
+        // a small move has to wait for the heartbeat
+        at_time(71_000);
+        contract.submit_round("NEAR/USD".to_string(), U128(2_021_000));
+
+        let latest = contract.get_latest("NEAR/USD".to_string()).unwrap();
+        assert_eq!((latest.round_id, latest.answer), (3, U128(2_021_000)));
+        assert_eq!(
+            contract
+                .get_round("NEAR/USD".to_string(), 2)
+                .unwrap()
+                .answer,
+            U128(2_020_000)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ROUND_NOT_DUE")]
+    fn feed_rejects_round_before_heartbeat_without_deviation() {
+        let mut contract = setup_feed();
+        at_time(10_000);
+        contract.submit_round("NEAR/USD".to_string(), U128(2_000_000));
+        at_time(11_000);
+        contract.submit_round("NEAR/USD".to_string(), U128(2_001_000));
+    }
+
+    #[test]
+    fn feed_history_is_a_bounded_ring_buffer() {
+        let mut contract = setup_feed();
+        let storage_before = contract.storage_balance_of(accounts(0)).unwrap();
+        for (timestamp, answer) in [(10_000, 100), (20_000, 200), (30_000, 300)] {
+            at_time(timestamp);
+            contract.submit_round("NEAR/USD".to_string(), U128(answer));
+        }
+        // history_size is 2, so the first round was overwritten
+        assert!(contract.get_round("NEAR/USD".to_string(), 1).is_none());
+        assert_eq!(
+            contract
+                .get_round("NEAR/USD".to_string(), 2)
+                .unwrap()
+                .answer,
+            U128(200)
+        );
+        let storage_after = contract.storage_balance_of(accounts(0)).unwrap();
+        assert!(storage_after.available.0 < storage_before.available.0);
+    }
+
+    #[test]
+    fn feed_twap_and_round_at() {
+        let mut contract = setup_feed();
+        at_time(10_000);
+        contract.submit_round("NEAR/USD".to_string(), U128(100));
+        at_time(80_000);
+        contract.submit_round("NEAR/USD".to_string(), U128(200));
+
+        at_time(100_000);
+        // 100 for 10s then 200 for 20s
+        assert_eq!(
+            contract.get_twap("NEAR/USD".to_string(), 30),
+            Some(U128(166))
+        );
+        assert_eq!(
+            contract.get_twap("NEAR/USD".to_string(), 10),
+            Some(U128(200))
+        );
+        // a window longer than the clock covers all kept history
+        assert_eq!(
+            contract.get_twap("NEAR/USD".to_string(), u64::MAX),
+            Some(U128(122))
+        );
+        assert_eq!(
+            contract
+                .get_round_at("NEAR/USD".to_string(), 50_000)
+                .unwrap()
+                .round_id,
+            1
+        );
+        assert!(contract
+            .get_round_at("NEAR/USD".to_string(), 5_000)
+            .is_none());
+    }
+
+    #[test]
+    fn fresh_reads_enforce_age_and_heartbeat() {
+        use crate::freshness::DataRef;
+
+        let mut contract = setup_feed();
+        let oracle_data = contract.create_oracle(
+            "https://example.com/fresh".to_string(),
+            "{}".to_string(),
+            None,
+            None,
+        );
+        at_time(10_000);
+        contract.execute_oracle(oracle_data.id.clone(), "42".to_string(), None, None, None);
+        contract.submit_round("NEAR/USD".to_string(), U128(100));
+
+        at_time(40_000);
+        let request = DataRef::Request(oracle_data.id);
+        let feed = DataRef::Feed("NEAR/USD".to_string());
+        assert_eq!(
+            contract.require_fresh(request.clone(), 30).value,
+            "42".to_string()
+        );
+        assert!(contract.get_fresh(request, 29).is_none());
+        assert_eq!(contract.require_fresh(feed.clone(), 60).value, "100");
+
+        // the feed heartbeat is 60s, so a generous max age does not help
+        at_time(80_000);
+        assert!(contract.get_fresh(feed, 3600).is_none());
+    }
+
+    #[test]
+    fn fresh_reads_report_why_data_is_unusable() {
+        use crate::freshness::DataRef;
+
+        let mut contract = setup_feed();
+        let feed = DataRef::Feed("NEAR/USD".to_string());
+        assert_eq!(contract.internal_fresh(&feed, 10), Err("ERR_NO_DATA"));
+        at_time(10_000);
+        contract.submit_round("NEAR/USD".to_string(), U128(100));
+        at_time(20_001);
+        assert_eq!(contract.internal_fresh(&feed, 10), Err("ERR_STALE_DATA"));
+        assert_eq!(
+            contract.internal_fresh(&DataRef::Request("unknown".to_string()), 10),
+            Err("ERR_ORACLE_NOT_FOUND")
+        );
+    }
+
+    #[test]
+    fn subscription_runs_debit_balance_and_refund_on_cancel() {
+        use near_sdk::mock::VmAction;
+        use near_sdk::test_utils::get_created_receipts;
+
+        let (mut contract, _) = setup_oracle();
+        let fee = contract.fee_per_call.0;
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(3 * fee)
+            .block_timestamp(10_000 * 1_000_000)
+            .build());
+        let subscription = contract.create_subscription(
+            feed::FeedSpec {
+                url: "https://example.com/price".to_string(),
+                data: "{}".to_string(),
+            },
+            60,
+            2,
+            None,
+        );
+
+        at_time(10_000);
+        contract.fulfill_subscription(subscription.id.clone(), "1".to_string());
+        assert!(contract.get_due_subscriptions(None, None).is_empty());
+        at_time(70_000);
+        let subscription = contract.fulfill_subscription(subscription.id, "2".to_string());
+        assert_eq!(subscription.runs, 2);
+        assert_eq!(subscription.result, Some("2".to_string()));
+        assert_eq!(subscription.balance, U128(fee));
+
+        assert_eq!(
+            contract.cancel_subscription(subscription.id.clone()),
+            U128(fee)
+        );
+        assert!(contract.get_subscription(subscription.id).is_none());
+        assert!(get_created_receipts().iter().any(|receipt| {
+            receipt.receiver_id == accounts(0)
+                && receipt.actions == vec![VmAction::Transfer { deposit: fee }]
+        }));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_SUBSCRIPTION_NOT_DUE")]
+    fn subscription_rejects_run_before_interval() {
+        let (mut contract, _) = setup_oracle();
+        let subscription = contract.create_subscription(
+            feed::FeedSpec {
+                url: "https://example.com/price".to_string(),
+                data: "{}".to_string(),
+            },
+            60,
+            5,
+            None,
+        );
+        at_time(10_000);
+        contract.fulfill_subscription(subscription.id.clone(), "1".to_string());
+        at_time(20_000);
+        contract.fulfill_subscription(subscription.id, "2".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_INTERVAL")]
+    fn subscription_rejects_interval_above_max() {
+        let (mut contract, _) = setup_oracle();
+        contract.create_subscription(
+            feed::FeedSpec {
+                url: "https://example.com/price".to_string(),
+                data: "{}".to_string(),
+            },
+            u64::MAX,
+            5,
+            None,
+        );
+    }
+
+    #[test]
+    fn subscription_with_huge_interval_stays_readable() {
+        let (mut contract, _) = setup_oracle();
+        let subscription = contract.create_subscription(
+            feed::FeedSpec {
+                url: "https://example.com/price".to_string(),
+                data: "{}".to_string(),
+            },
+            subscription::MAX_INTERVAL_SECS,
+            5,
+            None,
+        );
+        at_time(10_000);
+        let mut subscription = contract.fulfill_subscription(subscription.id, "1".to_string());
+        assert!(contract.get_due_subscriptions(None, None).is_empty());
+
+        // state written before the bound existed
+        subscription.interval_secs = u64::MAX;
+        contract
+            .subscriptions
+            .insert(&subscription.id, &subscription);
+        at_time(u64::MAX / 1_000_000);
+        assert!(contract.get_due_subscriptions(None, None).is_empty());
+    }
+
+    /// Context of a `resolve_*` callback chained to a consumer call that ended with `result`.
+    fn resolving(result: near_sdk::PromiseResult) {
+        use near_sdk::{RuntimeFeesConfig, VMConfig};
+
+        testing_env!(
+            VMContextBuilder::new()
+                .current_account_id(accounts(0))
+                .predecessor_account_id(accounts(0))
+                .block_timestamp(10_000 * 1_000_000)
+                .build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result],
+        );
+    }
+
+    #[test]
+    fn subscription_delivery_is_recorded() {
+        use near_sdk::PromiseResult;
+
+        let (mut contract, _) = setup_oracle();
+        let subscription = contract.create_subscription(
+            feed::FeedSpec {
+                url: "https://example.com/price".to_string(),
+                data: "{}".to_string(),
+            },
+            60,
+            5,
+            Some(callback::Callback {
+                contract_id: accounts(1),
+                method: "on_price".to_string(),
+                gas: 10_000_000_000_000.into(),
+            }),
+        );
+        at_time(10_000);
+        let subscription = contract.fulfill_subscription(subscription.id, "1".to_string());
+        assert_eq!(
+            subscription.delivery,
+            Some(callback::DeliveryStatus::Pending { attempts: 1 })
+        );
+        assert_eq!(callback_args(&accounts(1))["run"], json!(1));
+
+        resolving(PromiseResult::Failed);
+        assert!(!contract.resolve_subscription_delivery(subscription.id.clone(), 1));
+        assert_eq!(
+            contract
+                .get_subscription(subscription.id.clone())
+                .unwrap()
+                .delivery,
+            Some(callback::DeliveryStatus::DeliveryFailed { attempts: 1 })
+        );
+
+        // an outcome of an earlier run does not overwrite the latest one
+        at_time(70_000);
+        contract.fulfill_subscription(subscription.id.clone(), "2".to_string());
+        resolving(PromiseResult::Successful(vec![]));
+        contract.resolve_subscription_delivery(subscription.id.clone(), 1);
+        assert_eq!(
+            contract
+                .get_subscription(subscription.id.clone())
+                .unwrap()
+                .delivery,
+            Some(callback::DeliveryStatus::Pending { attempts: 1 })
+        );
+        contract.resolve_subscription_delivery(subscription.id.clone(), 2);
+        assert_eq!(
+            contract.get_subscription(subscription.id).unwrap().delivery,
+            Some(callback::DeliveryStatus::Delivered)
+        );
+    }
+
+    #[test]
+    fn subscription_failed_delivery_is_redelivered() {
+        use near_sdk::PromiseResult;
+
+        let (mut contract, _) = setup_oracle();
+        let subscription = contract.create_subscription(
+            feed::FeedSpec {
+                url: "https://example.com/price".to_string(),
+                data: "{}".to_string(),
+            },
+            60,
+            5,
+            Some(callback::Callback {
+                contract_id: accounts(1),
+                method: "on_price".to_string(),
+                gas: 10_000_000_000_000.into(),
+            }),
+        );
+        at_time(10_000);
+        contract.fulfill_subscription(subscription.id.clone(), "1".to_string());
+        resolving(PromiseResult::Failed);
+        contract.resolve_subscription_delivery(subscription.id.clone(), 1);
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .build());
+        assert_eq!(
+            contract
+                .redeliver_subscription(subscription.id.clone(), Some(20_000_000_000_000.into())),
+            callback::DeliveryStatus::Pending { attempts: 2 }
+        );
+        assert_eq!(
+            callback_args(&accounts(1)),
+            json!({ "id": subscription.id, "value": "1", "run": 1 })
+        );
+        resolving(PromiseResult::Failed);
+        contract.resolve_subscription_delivery(subscription.id.clone(), 1);
+        assert_eq!(
+            contract.get_subscription(subscription.id).unwrap().delivery,
+            Some(callback::DeliveryStatus::DeliveryFailed { attempts: 2 })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_DEPOSIT")]
+    fn subscription_top_up_requires_deposit() {
+        let (mut contract, _) = setup_oracle();
+        let subscription = contract.create_subscription(
+            feed::FeedSpec {
+                url: "https://example.com/price".to_string(),
+                data: "{}".to_string(),
+            },
+            60,
+            1,
+            None,
+        );
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .build());
+        contract.top_up_subscription(subscription.id);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_SUBSCRIPTION_FINISHED")]
+    fn subscription_top_up_rejects_finished_subscription() {
+        let (mut contract, _) = setup_oracle();
+        let subscription = contract.create_subscription(
+            feed::FeedSpec {
+                url: "https://example.com/price".to_string(),
+                data: "{}".to_string(),
+            },
+            60,
+            1,
+            None,
+        );
+        at_time(10_000);
+        contract.fulfill_subscription(subscription.id.clone(), "1".to_string());
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(contract.fee_per_call.0)
+            .build());
+        contract.top_up_subscription(subscription.id);
+    }
+
+    #[test]
+    fn vrf_matches_rfc_9381_vector() {
+        // ECVRF-EDWARDS25519-SHA512-TAI, example 16
+        let secret: [u8; 32] =
+            hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let public_key =
+            hex::decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                .unwrap();
+        let (proof, beta) = vrf::prove(&secret, b"");
+        assert_eq!(
+            hex::encode(&proof),
+            "8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab1268a1b0db10836d9826a528ca76567805"
+        );
+        assert_eq!(
+            hex::encode(beta),
+            "90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae"
+        );
+        assert_eq!(vrf::verify(&public_key, b"", &proof), Some(beta));
+        assert_eq!(vrf::verify(&public_key, b"other", &proof), None);
+    }
+
+    fn request_randomness(contract: &mut Contract) -> randomness::RandomnessRequest {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(contract.fee_per_call.0)
+            .block_index(10)
+            .random_seed([1u8; 32])
+            .build());
+        contract.request_randomness(
+            vec![9u8; 8].into(),
+            callback::Callback {
+                contract_id: accounts(1),
+                method: "on_random".to_string(),
+                gas: 10_000_000_000_000.into(),
+            },
+        )
+    }
+
+    #[test]
+    fn randomness_released_after_provider_proof() {
+        use near_sdk::PromiseResult;
+
+        let (mut contract, _) = setup_oracle();
+        let (_, public_key) = provider_keypair();
+        contract.add_data_provider(public_key.clone());
+        let request = request_randomness(&mut contract);
+        assert_eq!(request.block_seed.0, vec![1u8; 32]);
+        assert_eq!(request.provider, public_key);
+
+        let alpha = randomness::randomness_alpha(&request.id, &[9u8; 8], &[1u8; 32]);
+        let (proof, output) = vrf::prove(&[7u8; 32], &alpha);
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .block_index(11)
+            .build());
+        let fulfillment = contract.fulfill_randomness(request.id.clone(), proof.into());
+
+        let expected = hex::encode(&output[..32]);
+        assert_eq!(fulfillment.value, expected);
+        assert!(contract.get_pending_randomness(None, None).is_empty());
+        assert_eq!(
+            callback_args(&accounts(1)),
+            json!({ "id": request.id, "value": expected })
+        );
+
+        // a failed delivery is retried with the same value
+        resolving(PromiseResult::Failed);
+        assert!(!contract.resolve_randomness_delivery(request.id.clone()));
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .build());
+        assert_eq!(
+            contract.redeliver_randomness(request.id.clone(), Some(20_000_000_000_000.into())),
+            callback::DeliveryStatus::Pending { attempts: 2 }
+        );
+        assert_eq!(
+            callback_args(&accounts(1)),
+            json!({ "id": request.id, "value": expected })
+        );
+        resolving(PromiseResult::Failed);
+        contract.resolve_randomness_delivery(request.id.clone());
+        assert_eq!(
+            contract
+                .get_randomness_request(request.id)
+                .unwrap()
+                .delivery,
+            Some(callback::DeliveryStatus::DeliveryFailed { attempts: 2 })
+        );
+    }
+
+    #[test]
+    fn randomness_of_removed_provider_is_cancelled_with_refund() {
+        let (mut contract, _) = setup_oracle();
+        let (_, public_key) = provider_keypair();
+        contract.add_data_provider(public_key.clone());
+        let request = request_randomness(&mut contract);
+        let available = contract.storage_balance_of(accounts(0)).unwrap().available;
+
+        contract.remove_data_provider(public_key);
+        assert_eq!(
+            contract.cancel_randomness(request.id.clone()),
+            U128(contract.fee_per_call.0)
+        );
+        assert!(contract.get_randomness_request(request.id).is_none());
+        assert!(
+            contract
+                .storage_balance_of(accounts(0))
+                .unwrap()
+                .available
+                .0
+                > available.0
+        );
+    }
+
+    #[test]
+    fn randomness_is_cancelled_once_stale() {
+        let (mut contract, _) = setup_oracle();
+        let (_, public_key) = provider_keypair();
+        contract.add_data_provider(public_key);
+        let request = request_randomness(&mut contract);
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .block_index(10 + randomness::RANDOMNESS_CANCEL_AFTER_BLOCKS)
+            .build());
+        contract.cancel_randomness(request.id.clone());
+        assert!(contract.get_randomness_request(request.id).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RANDOMNESS_PROVIDER_ACTIVE")]
+    fn randomness_cancel_waits_for_active_provider() {
+        let (mut contract, _) = setup_oracle();
+        let (_, public_key) = provider_keypair();
+        contract.add_data_provider(public_key);
+        let request = request_randomness(&mut contract);
+        contract.cancel_randomness(request.id);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_PROOF")]
+    fn randomness_rejects_proof_over_other_seed() {
+        let (mut contract, _) = setup_oracle();
+        let (_, public_key) = provider_keypair();
+        contract.add_data_provider(public_key);
+        let request = request_randomness(&mut contract);
+
+        let alpha = randomness::randomness_alpha(&request.id, &[0u8; 8], &[1u8; 32]);
+        testing_env!(VMContextBuilder::new().block_index(11).build());
+        contract.fulfill_randomness(request.id, vrf::prove(&[7u8; 32], &alpha).0.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOTHING_TO_REDELIVER")]
+    fn randomness_redelivery_requires_failed_delivery() {
+        let (mut contract, _) = setup_oracle();
+        let (_, public_key) = provider_keypair();
+        contract.add_data_provider(public_key);
+        let request = request_randomness(&mut contract);
+        contract.redeliver_randomness(request.id, None);
+    }
+
+    fn sign_report(id: &str, value: &str, timestamp: u64) -> (String, report::SignedReport) {
+        let secret = libsecp256k1::SecretKey::parse(&[9u8; 32]).unwrap();
+        let public = libsecp256k1::PublicKey::from_secret_key(&secret);
+        let address = env::keccak256_array(&public.serialize()[1..])[12..].to_vec();
+
+        let message = libsecp256k1::Message::parse(&report::report_message(id, value, timestamp));
+        let (signature, recovery_id) = libsecp256k1::sign(&message, &secret);
+        let mut signature = signature.serialize().to_vec();
+        signature.push(recovery_id.serialize() + 27);
+        (
+            format!("0x{}", hex::encode(address)),
+            report::SignedReport {
+                id: id.to_string(),
+                value: value.to_string(),
+                timestamp,
+                signature: format!("0x{}", hex::encode(signature)),
+            },
+        )
+    }
+
+    #[test]
+    fn submit_signed_report_from_publisher() {
+        let (mut contract, oracle_data) = setup_oracle();
+        let (address, report) = sign_report(&oracle_data.id, "1.23", oracle_data.timestamp);
+        contract.add_publisher(address.clone());
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(2_000_000_000)
+            .build());
+        let executed = contract.submit_signed_report(report);
+        assert_eq!(executed.return_value, Some("1.23".to_string()));
+        assert_eq!(executed.publisher, Some(address));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_STALE_REPORT")]
+    fn submit_signed_report_rejects_stale_report() {
+        let (mut contract, oracle_data) = setup_oracle();
+        let (address, report) = sign_report(&oracle_data.id, "1.23", oracle_data.timestamp);
+        contract.add_publisher(address);
+
+        testing_env!(VMContextBuilder::new()
+            .block_timestamp(
+                (oracle_data.timestamp + report::DEFAULT_MAX_REPORT_AGE_MS + 1) * 1_000_000
+            )
+            .build());
+        contract.submit_signed_report(report);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_WHITELISTED")]
+    fn execute_oracle_requires_whitelist_without_attestation() {
+        let (mut contract, oracle_data) = setup_oracle();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.execute_oracle(oracle_data.id, "42".to_string(), None, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_CREATOR_OR_OWNER")]
+    fn delete_oracle_requires_creator_or_owner() {
+        let (mut contract, oracle_data) = setup_oracle();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .build());
+        contract.delete_oracle(oracle_data.id);
+    }
+
+    #[test]
+    fn refused_delete_keeps_the_request() {
+        let (mut contract, oracle_data) = setup_oracle();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .build());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            contract.delete_oracle_batch(vec![oracle_data.id.clone()])
+        }));
+        assert!(result.is_err());
+        assert!(contract.get_queued_data_by_id(oracle_data.id).is_some());
+    }
+
+    #[test]
+    fn delete_oracle_batch_by_owner() {
+        let (mut contract, _) = setup_oracle();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(ONE_NEAR / 10)
+            .build());
+        contract.storage_deposit(None, None);
+        let oracle_data = contract.create_oracle(
+            "https://example.com/other".to_string(),
+            "{}".to_string(),
+            None,
+            None,
+        );
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .build());
+        contract.delete_oracle_batch(vec![oracle_data.id.clone()]);
+        assert!(contract.get_queued_data_by_id(oracle_data.id).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ALREADY_EXECUTED")]
+    fn execute_oracle_rejects_executed_request() {
+        let (mut contract, oracle_data) = setup_oracle();
+        contract.execute_oracle(oracle_data.id.clone(), "42".to_string(), None, None, None);
+        contract.execute_oracle(oracle_data.id, "43".to_string(), None, None, None);
+    }
+
+    #[test]
+    fn execute_oracle_batch_skips_executed_requests() {
+        let (mut contract, executed) = setup_oracle();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(contract.fee_per_call.0)
+            .block_timestamp(1_000_000_000)
+            .random_seed([1u8; 32])
+            .build());
+        let pending = contract.create_oracle(
+            "https://example.com/b".to_string(),
+            "{}".to_string(),
+            None,
+            None,
+        );
+        contract.execute_oracle(executed.id.clone(), "42".to_string(), None, None, None);
+        contract.execute_oracle_batch(
+            vec![executed.id.clone(), pending.id.clone()],
+            vec!["43".to_string(), "44".to_string()],
+        );
+        let value = |id: String| contract.get_queued_data_by_id(id).unwrap().return_value;
+        assert_eq!(value(executed.id), Some("42".to_string()));
+        assert_eq!(value(pending.id), Some("44".to_string()));
+    }
+
+    #[test]
+    fn execute_oracle_batch_skips_requests_it_cannot_execute() {
+        let (mut contract, valid) = setup_oracle();
+        let with_seed = |seed: u8| {
+            testing_env!(VMContextBuilder::new()
+                .predecessor_account_id(accounts(0))
+                .attached_deposit(ONE_NEAR / 10)
+                .block_timestamp(1_000_000_000)
+                .random_seed([seed; 32])
+                .build());
+        };
+        with_seed(1);
+        let borsh = contract.create_oracle(
+            "https://example.com/borsh".to_string(),
+            "{}".to_string(),
+            None,
+            Some(encoding::ResultEncoding::Borsh {
+                value_type: encoding::ValueType::U64,
+            }),
+        );
+        with_seed(2);
+        let expired = contract.create_oracle_await(
+            "https://example.com/await".to_string(),
+            "{}".to_string(),
+            None,
+            Some(1_000),
+            None,
+        );
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .block_timestamp((expired.timestamp + 1_001) * 1_000_000)
+            .build());
+        contract.execute_oracle_batch(
+            vec![
+                "deleted".to_string(),
+                expired.id.clone(),
+                borsh.id.clone(),
+                valid.id.clone(),
+            ],
+            vec![
+                "1".to_string(),
+                "2".to_string(),
+                "4.2".to_string(),
+                "42".to_string(),
+            ],
+        );
+        let executed = |id: String| contract.get_queued_data_by_id(id).unwrap().executed;
+        assert!(!executed(expired.id.clone()));
+        assert!(!executed(borsh.id.clone()));
+        assert!(executed(valid.id));
+        assert_eq!(
+            near_sdk::test_utils::get_logs()
+                .into_iter()
+                .filter(|log| log.starts_with("Skipping"))
+                .collect::<Vec<_>>(),
+            vec![
+                "Skipping deleted: ERR_ORACLE_NOT_FOUND".to_string(),
+                format!("Skipping {}: ERR_ORACLE_EXPIRED", expired.id),
+                format!("Skipping {}: ERR_INVALID_VALUE_FOR_ENCODING", borsh.id),
+            ]
+        );
+    }
+
+    #[test]
+    fn migrate_rewrites_v0_state() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(0))
+            .build());
+        let mut old = migrate::ContractV0 {
+            owner_id: accounts(1),
+            queued_data: UnorderedMap::new(StorageKey::OracleData.try_to_vec().unwrap()),
+            accounts: LookupMap::new(StorageKey::Accounts.try_to_vec().unwrap()),
+            account_storage_usage: 7,
+            whitelisted: LookupSet::new(StorageKey::Whitelisted.try_to_vec().unwrap()),
+            fee_per_call: U128(5),
+        };
+        old.queued_data.insert(
+            &"a".to_string(),
+            &migrate::OracleDataV0 {
+                id: "a".to_string(),
+                url: "https://example.com".to_string(),
+                data: "{}".to_string(),
+                timestamp: 1,
+                executed: true,
+                return_value: Some("42".to_string()),
+                creator: accounts(2),
+            },
+        );
+        old.whitelisted.insert(&accounts(3));
+        env::state_write(&old);
+
+        let contract = Contract::migrate();
+        assert_eq!(contract.owner_id, accounts(1));
+        assert_eq!(contract.fee_per_call, U128(5));
+        assert!(contract.is_whitelisted(accounts(3)));
+        let queued = contract.get_queued_data(None, None);
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].return_value.as_deref(), Some("42"));
+        assert_eq!(queued[0].creator, accounts(2));
+        assert!(queued[0].callback.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ALREADY_MIGRATED")]
+    fn migrate_rejects_current_state() {
+        let (contract, _) = setup_oracle();
+        env::state_write(&contract);
+        Contract::migrate();
+    }
 }
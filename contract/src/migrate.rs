@@ -0,0 +1,97 @@
+use crate::*;
+
+/// Version of the state layout. It is kept under its own storage key, apart from the
+/// contract struct, so `migrate` can tell which layout it is about to read.
+pub const STATE_VERSION: u32 = 1;
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+
+/// `OracleData` as stored by the first deployed version.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OracleDataV0 {
+    pub id: String,
+    pub url: String,
+    pub data: String,
+    pub timestamp: u64,
+    pub executed: bool,
+    pub return_value: Option<String>,
+    pub creator: AccountId,
+}
+
+/// `Contract` as stored by the first deployed version, which wrote no state version.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV0 {
+    pub owner_id: AccountId,
+    pub queued_data: UnorderedMap<String, OracleDataV0>,
+    pub accounts: LookupMap<AccountId, StorageBalance>,
+    pub account_storage_usage: StorageUsage,
+    pub whitelisted: LookupSet<AccountId>,
+    pub fee_per_call: U128,
+}
+
+impl From<OracleDataV0> for OracleData {
+    fn from(oracle_data: OracleDataV0) -> Self {
+        Self {
+            id: oracle_data.id,
+            url: oracle_data.url,
+            data: oracle_data.data,
+            timestamp: oracle_data.timestamp,
+            executed: oracle_data.executed,
+            return_value: oracle_data.return_value,
+            creator: oracle_data.creator,
+            attestation: None,
+            publisher: None,
+            evidence: None,
+            callback: None,
+            delivery: None,
+            deadline: None,
+            error: None,
+            executed_at: None,
+            encoding: None,
+            transform: None,
+        }
+    }
+}
+
+pub fn write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &STATE_VERSION.to_le_bytes());
+}
+
+fn state_version() -> u32 {
+    env::storage_read(STATE_VERSION_KEY).map_or(0, |version| {
+        u32::from_le_bytes(version.try_into().expect("ERR_INVALID_STATE_VERSION"))
+    })
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Upgrades state written by an older version of the contract to the current layout.
+    /// Call it in the same transaction as the deploy. Every queued request is rewritten
+    /// in this one call, so the queue has to fit in one call's gas.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let version = state_version();
+        require!(version != STATE_VERSION, "ERR_ALREADY_MIGRATED");
+        require!(version == 0, "ERR_UNKNOWN_STATE_VERSION");
+        Self::internal_migrate_v0()
+    }
+}
+
+impl Contract {
+    fn internal_migrate_v0() -> Self {
+        let mut old: ContractV0 = env::state_read().expect("ERR_NO_STATE");
+        let queued = old.queued_data.to_vec();
+        // the new map reuses the prefix, so the old entries go first
+        old.queued_data.clear();
+
+        let mut contract = Self::new(old.owner_id);
+        contract.accounts = old.accounts;
+        contract.account_storage_usage = old.account_storage_usage;
+        contract.whitelisted = old.whitelisted;
+        contract.fee_per_call = old.fee_per_call;
+        for (id, oracle_data) in queued {
+            contract.queued_data.insert(&id, &oracle_data.into());
+        }
+        contract
+    }
+}
@@ -0,0 +1,172 @@
+use crate::callback::{
+    consumer_call_result, resolved_delivery, retry_delivery, Callback, DeliveryStatus,
+};
+use crate::*;
+use near_sdk::json_types::{Base64VecU8, U64};
+use near_sdk::serde_json::json;
+use near_sdk::Promise;
+
+pub use near_oracle_types::randomness::{
+    randomness_alpha, RandomnessFulfillment, RandomnessRequest,
+};
+
+/// Blocks after which the requester may cancel a request its provider left unfulfilled:
+/// about a day.
+pub const RANDOMNESS_CANCEL_AFTER_BLOCKS: u64 = 86_400;
+
+impl Contract {
+    fn internal_deliver_randomness(&self, request: &RandomnessRequest, callback: &Callback) {
+        let value = request
+            .fulfillment
+            .as_ref()
+            .map(|fulfillment| fulfillment.value.clone());
+        let args = json!({ "id": request.id, "value": value });
+        self.internal_call_consumer(
+            callback,
+            args.to_string().into_bytes(),
+            Self::ext_resolve().resolve_randomness_delivery(request.id.clone()),
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Requests a random value that is pushed to `callback` as `{ "id", "value" }` (`value`
+    /// being 32 hex encoded bytes) once the assigned data provider fulfils it.
+    #[payable]
+    pub fn request_randomness(
+        &mut self,
+        seed: Base64VecU8,
+        callback: Callback,
+    ) -> RandomnessRequest {
+        require!(
+            env::attached_deposit() >= self.fee_per_call.0,
+            "ERR_NOT_ENOUGH_DEPOSIT"
+        );
+        callback.assert_valid();
+        let providers = self.data_providers.as_vector();
+        require!(!providers.is_empty(), "ERR_NO_DATA_PROVIDER");
+        let init_storage = env::storage_usage();
+        let provider = providers
+            .get(self.randomness_nonce % providers.len())
+            .unwrap();
+        self.randomness_nonce += 1;
+        let request = RandomnessRequest {
+            id: format!("rand-{}", self.randomness_nonce),
+            requester: env::predecessor_account_id(),
+            seed,
+            block_seed: env::random_seed().into(),
+            block_height: env::block_height(),
+            provider,
+            fee: U128(env::attached_deposit()),
+            callback,
+            fulfillment: None,
+            delivery: None,
+        };
+        self.randomness_requests.insert(&request.id, &request);
+        self.cal_storage(init_storage, &request.requester);
+        request
+    }
+    /// Verifies the assigned provider's VRF proof over the request and releases its output
+    /// to the consumer. Anyone may relay the proof.
+    pub fn fulfill_randomness(&mut self, id: String, proof: Base64VecU8) -> RandomnessFulfillment {
+        let init_storage = env::storage_usage();
+        let mut request = self
+            .randomness_requests
+            .get(&id)
+            .expect("ERR_RANDOMNESS_NOT_FOUND");
+        require!(
+            request.fulfillment.is_none(),
+            "ERR_RANDOMNESS_ALREADY_FULFILLED"
+        );
+        let alpha = randomness_alpha(&id, &request.seed.0, &request.block_seed.0);
+        let output = vrf::verify(&request.provider.as_bytes()[1..], &alpha, &proof.0)
+            .expect("ERR_INVALID_PROOF");
+
+        let fulfillment = RandomnessFulfillment {
+            proof,
+            value: hex::encode(&output[..32]),
+            fulfilled_at: env::block_timestamp_ms(),
+        };
+        request.fulfillment = Some(fulfillment.clone());
+        request.delivery = Some(DeliveryStatus::Pending { attempts: 1 });
+        self.randomness_requests.insert(&id, &request);
+        self.cal_storage(init_storage, &request.requester);
+        self.internal_deliver_randomness(&request, &request.callback);
+        fulfillment
+    }
+    /// Removes an unfulfilled request and refunds its fee to the requester, once its provider
+    /// is no longer registered or `RANDOMNESS_CANCEL_AFTER_BLOCKS` after it was made.
+    pub fn cancel_randomness(&mut self, id: String) -> U128 {
+        let init_storage = env::storage_usage();
+        let request = self
+            .randomness_requests
+            .get(&id)
+            .expect("ERR_RANDOMNESS_NOT_FOUND");
+        require!(
+            request.requester == env::predecessor_account_id(),
+            "ERR_NOT_RANDOMNESS_REQUESTER"
+        );
+        require!(
+            request.fulfillment.is_none(),
+            "ERR_RANDOMNESS_ALREADY_FULFILLED"
+        );
+        let stale = env::block_height()
+            >= request
+                .block_height
+                .saturating_add(RANDOMNESS_CANCEL_AFTER_BLOCKS);
+        require!(
+            stale || !self.data_providers.contains(&request.provider),
+            "ERR_RANDOMNESS_PROVIDER_ACTIVE"
+        );
+        self.randomness_requests.remove(&id);
+        self.cal_storage(init_storage, &request.requester);
+        if request.fee.0 > 0 {
+            Promise::new(request.requester).transfer(request.fee.0);
+        }
+        request.fee
+    }
+    /// Records the outcome of delivering the random value to the request's callback.
+    #[private]
+    pub fn resolve_randomness_delivery(&mut self, id: String) -> bool {
+        let call_result = consumer_call_result();
+        if let Some(mut request) = self.randomness_requests.get(&id) {
+            request.delivery = Some(resolved_delivery(&request.delivery, &call_result));
+            self.randomness_requests.insert(&id, &request);
+        }
+        call_result.is_ok()
+    }
+    /// Retries a failed delivery of the random value, like `redeliver`.
+    pub fn redeliver_randomness(&mut self, id: String, gas: Option<U64>) -> DeliveryStatus {
+        let mut request = self
+            .randomness_requests
+            .get(&id)
+            .expect("ERR_RANDOMNESS_NOT_FOUND");
+        let (delivery, callback) = retry_delivery(&request.delivery, Some(&request.callback), gas);
+        request.delivery = Some(delivery.clone());
+        self.randomness_requests.insert(&id, &request);
+        self.internal_deliver_randomness(&request, &callback);
+        delivery
+    }
+}
+//view
+#[near_bindgen]
+impl Contract {
+    pub fn get_randomness_request(&self, id: String) -> Option<RandomnessRequest> {
+        self.randomness_requests.get(&id)
+    }
+    /// Randomness requests still waiting for a provider.
+    pub fn get_pending_randomness(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<RandomnessRequest> {
+        self.randomness_requests
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(_, request)| request)
+            .filter(|request| request.fulfillment.is_none())
+            .collect()
+    }
+}
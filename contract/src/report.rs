@@ -0,0 +1,114 @@
+use crate::attestation::signing_payload;
+use crate::*;
+
+/// 20-byte Ethereum address of a registered report publisher.
+pub type EthAddress = [u8; 20];
+
+pub use near_oracle_types::report::SignedReport;
+
+pub const DEFAULT_MAX_REPORT_AGE_MS: u64 = 5 * 60 * 1000;
+
+/// Ethereum `personal_sign` digest of `keccak256(signing_payload(..))`, as produced by
+/// `signMessage` in ethers/web3.
+pub fn report_message(id: &str, value: &str, timestamp: u64) -> [u8; 32] {
+    let mut message = b"\x19Ethereum Signed Message:\n32".to_vec();
+    message.extend_from_slice(&env::keccak256_array(&signing_payload(
+        id, value, timestamp,
+    )));
+    env::keccak256_array(&message)
+}
+
+pub fn parse_eth_address(address: &str) -> EthAddress {
+    hex::decode(address.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .unwrap_or_else(|| env::panic_str("ERR_INVALID_ETH_ADDRESS"))
+}
+
+pub fn format_eth_address(address: &EthAddress) -> String {
+    format!("0x{}", hex::encode(address))
+}
+
+/// Recovers the address that signed `hash`, or `None` for malformed or malleable signatures.
+pub fn recover_eth_address(hash: &[u8; 32], signature: &[u8]) -> Option<EthAddress> {
+    if signature.len() != 65 {
+        return None;
+    }
+    let v = match signature[64] {
+        v @ 0..=1 => v,
+        v @ 27..=28 => v - 27,
+        _ => return None,
+    };
+    let public_key = env::ecrecover(hash, &signature[..64], v, true)?;
+    let hash = env::keccak256_array(&public_key);
+    hash[12..].try_into().ok()
+}
+
+//owner
+#[near_bindgen]
+impl Contract {
+    pub fn add_publisher(&mut self, address: String) -> bool {
+        self.assert_owner();
+        self.publishers.insert(&parse_eth_address(&address))
+    }
+    pub fn remove_publisher(&mut self, address: String) -> bool {
+        self.assert_owner();
+        self.publishers.remove(&parse_eth_address(&address))
+    }
+    pub fn set_max_report_age(&mut self, max_report_age_ms: u64) {
+        self.assert_owner();
+        self.max_report_age_ms = max_report_age_ms;
+    }
+}
+//view
+#[near_bindgen]
+impl Contract {
+    pub fn is_publisher(&self, address: String) -> bool {
+        self.publishers.contains(&parse_eth_address(&address))
+    }
+    pub fn get_publishers(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<String> {
+        self.publishers
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|address| format_eth_address(&address))
+            .collect()
+    }
+    pub fn get_max_report_age(&self) -> u64 {
+        self.max_report_age_ms
+    }
+}
+#[near_bindgen]
+impl Contract {
+    /// Fulfils a queued request with a publisher-signed value; any account may relay it.
+    pub fn submit_signed_report(&mut self, report: SignedReport) -> OracleData {
+        let mut oracle_data = self
+            .queued_data
+            .get(&report.id)
+            .expect("ERR_ORACLE_NOT_FOUND");
+        let now = env::block_timestamp_ms();
+        require!(
+            report.timestamp >= oracle_data.timestamp && report.timestamp <= now,
+            "ERR_INVALID_REPORT_TIMESTAMP"
+        );
+        require!(
+            now - report.timestamp <= self.max_report_age_ms,
+            "ERR_STALE_REPORT"
+        );
+
+        let signature = hex::decode(report.signature.trim_start_matches("0x"))
+            .unwrap_or_else(|_| env::panic_str("ERR_INVALID_SIGNATURE"));
+        let publisher = recover_eth_address(
+            &report_message(&report.id, &report.value, report.timestamp),
+            &signature,
+        )
+        .unwrap_or_else(|| env::panic_str("ERR_INVALID_SIGNATURE"));
+        require!(
+            self.publishers.contains(&publisher),
+            "ERR_UNKNOWN_PUBLISHER"
+        );
+
+        oracle_data.publisher = Some(format_eth_address(&publisher));
+        self.internal_execute_oracle(oracle_data, report.value)
+    }
+}
@@ -16,8 +16,8 @@ impl Contract {
             self.accounts.insert(
                 account_id,
                 &StorageBalance {
-                    total: U128(amount.clone()),
-                    available: U128(amount.clone()),
+                    total: U128(*amount),
+                    available: U128(*amount),
                 },
             );
         } else {
@@ -55,7 +55,7 @@ impl Contract {
     }
 
     fn internal_storage_balance_of(&self, account_id: &AccountId) -> Option<StorageBalance> {
-        self.accounts.get(&account_id)
+        self.accounts.get(account_id)
     }
 }
 #[near_bindgen]
@@ -1,5 +1,5 @@
+#[allow(clippy::module_inception)]
 pub mod storage;
 pub use storage::*;
 pub mod cal_storage;
 pub mod impl_storage;
-pub use cal_storage::*;
@@ -1,23 +1,7 @@
-use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::json_types::U128;
-use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::AccountId;
 pub const MIN_STORAGE_BALANCE: u128 = 100_000_000_000_000_000_000_000;
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
-#[serde(crate = "near_sdk::serde")]
-#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
-pub struct StorageBalance {
-    pub total: U128,
-    pub available: U128,
-}
-
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
-#[serde(crate = "near_sdk::serde")]
-#[cfg_attr(feature = "abi", derive(schemars::JsonSchema))]
-pub struct StorageBalanceBounds {
-    pub min: U128,
-    pub max: Option<U128>,
-}
+pub use near_oracle_types::{StorageBalance, StorageBalanceBounds};
 
 pub trait StorageManagement {
     // if `registration_only=true` MUST refund above the minimum balance if the account didn't exist and
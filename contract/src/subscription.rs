@@ -0,0 +1,204 @@
+use crate::callback::{
+    consumer_call_result, resolved_delivery, retry_delivery, Callback, DeliveryStatus,
+};
+use crate::feed::FeedSpec;
+use crate::*;
+use near_sdk::json_types::U64;
+use near_sdk::serde_json::json;
+use near_sdk::Promise;
+
+pub use near_oracle_types::Subscription;
+
+/// Longest interval a subscription may run at: one year.
+pub const MAX_INTERVAL_SECS: u64 = 365 * 24 * 60 * 60;
+
+impl Contract {
+    fn internal_deliver_subscription(&self, subscription: &Subscription, callback: &Callback) {
+        let args = json!({
+            "id": subscription.id,
+            "value": subscription.result,
+            "run": subscription.runs,
+        });
+        self.internal_call_consumer(
+            callback,
+            args.to_string().into_bytes(),
+            Self::ext_resolve()
+                .resolve_subscription_delivery(subscription.id.clone(), subscription.runs),
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Creates a subscription funded by the attached deposit. Storage is charged to the
+    /// caller's storage balance.
+    #[payable]
+    pub fn create_subscription(
+        &mut self,
+        spec: FeedSpec,
+        interval_secs: u64,
+        max_runs: u32,
+        callback: Option<Callback>,
+    ) -> Subscription {
+        require!(
+            env::attached_deposit() >= self.fee_per_call.0,
+            "ERR_NOT_ENOUGH_DEPOSIT"
+        );
+        require!(
+            interval_secs > 0 && interval_secs <= MAX_INTERVAL_SECS,
+            "ERR_INVALID_INTERVAL"
+        );
+        require!(max_runs > 0, "ERR_INVALID_MAX_RUNS");
+        if let Some(callback) = &callback {
+            callback.assert_valid();
+        }
+        let init_storage = env::storage_usage();
+        self.subscription_nonce += 1;
+        let subscription = Subscription {
+            id: format!("sub-{}", self.subscription_nonce),
+            owner: env::predecessor_account_id(),
+            spec,
+            interval_secs,
+            max_runs,
+            runs: 0,
+            balance: U128(env::attached_deposit()),
+            last_run: None,
+            result: None,
+            callback,
+            delivery: None,
+        };
+        self.subscriptions.insert(&subscription.id, &subscription);
+        self.cal_storage(init_storage, &subscription.owner);
+        subscription
+    }
+    /// Adds the attached deposit to the balance of a subscription that has runs left.
+    #[payable]
+    pub fn top_up_subscription(&mut self, id: String) -> Subscription {
+        require!(env::attached_deposit() > 0, "ERR_NOT_ENOUGH_DEPOSIT");
+        let mut subscription = self
+            .subscriptions
+            .get(&id)
+            .expect("ERR_SUBSCRIPTION_NOT_FOUND");
+        require!(
+            subscription.runs < subscription.max_runs,
+            "ERR_SUBSCRIPTION_FINISHED"
+        );
+        subscription.balance.0 += env::attached_deposit();
+        self.subscriptions.insert(&id, &subscription);
+        subscription
+    }
+    /// Removes the subscription and refunds its remaining balance to the owner.
+    pub fn cancel_subscription(&mut self, id: String) -> U128 {
+        let init_storage = env::storage_usage();
+        let subscription = self
+            .subscriptions
+            .get(&id)
+            .expect("ERR_SUBSCRIPTION_NOT_FOUND");
+        require!(
+            subscription.owner == env::predecessor_account_id(),
+            "ERR_NOT_SUBSCRIPTION_OWNER"
+        );
+        self.subscriptions.remove(&id);
+        self.cal_storage(init_storage, &subscription.owner);
+        if subscription.balance.0 > 0 {
+            Promise::new(subscription.owner).transfer(subscription.balance.0);
+        }
+        subscription.balance
+    }
+}
+//operator
+#[near_bindgen]
+impl Contract {
+    /// Records a due run, debiting `fee_per_call` and pushing the value to the callback.
+    pub fn fulfill_subscription(&mut self, id: String, value: String) -> Subscription {
+        self.assert_whitelisted();
+        let init_storage = env::storage_usage();
+        let mut subscription = self
+            .subscriptions
+            .get(&id)
+            .expect("ERR_SUBSCRIPTION_NOT_FOUND");
+        let now = env::block_timestamp_ms();
+        require!(subscription.is_due(now), "ERR_SUBSCRIPTION_NOT_DUE");
+        subscription.balance.0 = subscription
+            .balance
+            .0
+            .checked_sub(self.fee_per_call.0)
+            .expect("ERR_SUBSCRIPTION_BALANCE_EXHAUSTED");
+        subscription.runs += 1;
+        subscription.last_run = Some(now);
+        subscription.result = Some(value);
+        if subscription.callback.is_some() {
+            subscription.delivery = Some(DeliveryStatus::Pending { attempts: 1 });
+        }
+        self.subscriptions.insert(&id, &subscription);
+        self.cal_storage(init_storage, &subscription.owner);
+
+        if let Some(callback) = &subscription.callback {
+            self.internal_deliver_subscription(&subscription, callback);
+        }
+        subscription
+    }
+    /// Records the outcome of delivering run `run` to the subscription's callback. Outcomes
+    /// that arrive after a later run was recorded are ignored.
+    #[private]
+    pub fn resolve_subscription_delivery(&mut self, id: String, run: u32) -> bool {
+        let call_result = consumer_call_result();
+        if let Some(mut subscription) = self.subscriptions.get(&id) {
+            if subscription.runs == run {
+                subscription.delivery =
+                    Some(resolved_delivery(&subscription.delivery, &call_result));
+                self.subscriptions.insert(&id, &subscription);
+            }
+        }
+        call_result.is_ok()
+    }
+    /// Retries a failed delivery of the latest run's value, like `redeliver`.
+    pub fn redeliver_subscription(&mut self, id: String, gas: Option<U64>) -> DeliveryStatus {
+        let mut subscription = self
+            .subscriptions
+            .get(&id)
+            .expect("ERR_SUBSCRIPTION_NOT_FOUND");
+        let (delivery, callback) =
+            retry_delivery(&subscription.delivery, subscription.callback.as_ref(), gas);
+        subscription.delivery = Some(delivery.clone());
+        self.subscriptions.insert(&id, &subscription);
+        self.internal_deliver_subscription(&subscription, &callback);
+        delivery
+    }
+}
+//view
+#[near_bindgen]
+impl Contract {
+    pub fn get_subscription(&self, id: String) -> Option<Subscription> {
+        self.subscriptions.get(&id)
+    }
+    pub fn get_subscriptions(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<Subscription> {
+        self.subscriptions
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(_, subscription)| subscription)
+            .collect()
+    }
+    /// Subscriptions an operator can fulfil now.
+    pub fn get_due_subscriptions(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<Subscription> {
+        let now = env::block_timestamp_ms();
+        self.subscriptions
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(_, subscription)| subscription)
+            .filter(|subscription| {
+                subscription.is_due(now) && subscription.balance.0 >= self.fee_per_call.0
+            })
+            .collect()
+    }
+}
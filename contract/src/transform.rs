@@ -0,0 +1,49 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde_json::Value;
+
+pub use near_oracle_types::transform::MAX_TRANSFORM_SIZE;
+
+#[near_bindgen]
+impl Contract {
+    /// Stores a WASM transform module, returning its hex SHA-256 hash. A request opts into it
+    /// with `"transform": "<hash>"` in its `data`; operators run the module over the
+    /// response and submit its output as the value. Registering a known module is a no-op.
+    pub fn register_transform(&mut self, module: Base64VecU8) -> String {
+        let module: Vec<u8> = module.into();
+        require!(
+            !module.is_empty() && module.len() <= MAX_TRANSFORM_SIZE,
+            "ERR_INVALID_TRANSFORM_SIZE"
+        );
+        let hash = hex::encode(env::sha256(&module));
+        if !self.transforms.contains_key(&hash) {
+            let init_storage = env::storage_usage();
+            self.transforms.insert(&hash, &module);
+            self.cal_storage(init_storage, &env::predecessor_account_id());
+        }
+        hash
+    }
+}
+
+//view
+#[near_bindgen]
+impl Contract {
+    pub fn get_transform(&self, hash: String) -> Option<Base64VecU8> {
+        self.transforms.get(&hash).map(Base64VecU8::from)
+    }
+}
+
+impl Contract {
+    /// The transform named by a request's `data`, which must be registered.
+    pub fn internal_request_transform(&self, data: &Value) -> Option<String> {
+        let hash = data
+            .get("transform")?
+            .as_str()
+            .expect("ERR_INVALID_TRANSFORM");
+        require!(
+            self.transforms.contains_key(&hash.to_string()),
+            "ERR_TRANSFORM_NOT_FOUND"
+        );
+        Some(hash.to_string())
+    }
+}
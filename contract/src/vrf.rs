@@ -0,0 +1,110 @@
+//! ECVRF-EDWARDS25519-SHA512-TAI (RFC 9381) verification, over the same ed25519 keys the
+//! data providers register.
+//!
+//! Unlike an ed25519 signature, a VRF proof has exactly one valid output per key and input,
+//! so the key holder cannot pick among outputs.
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use sha2::{Digest, Sha512};
+
+const SUITE: u8 = 0x03;
+/// Encoded `Gamma || c || s`.
+pub const PROOF_LEN: usize = 80;
+
+fn decode_point(bytes: &[u8]) -> Option<EdwardsPoint> {
+    CompressedEdwardsY::from_slice(bytes).decompress()
+}
+
+fn scalar_from_challenge(c: &[u8]) -> Scalar {
+    let mut bytes = [0u8; 32];
+    bytes[..16].copy_from_slice(c);
+    Scalar::from_bytes_mod_order(bytes)
+}
+
+/// `ECVRF_encode_to_curve_try_and_increment` with the public key as salt.
+fn encode_to_curve(public_key: &[u8; 32], alpha: &[u8]) -> EdwardsPoint {
+    (0..=u8::MAX)
+        .find_map(|ctr| {
+            let hash = Sha512::new()
+                .chain([SUITE, 0x01])
+                .chain(public_key)
+                .chain(alpha)
+                .chain([ctr, 0x00])
+                .finalize();
+            decode_point(&hash[..32]).map(|point| point.mul_by_cofactor())
+        })
+        // each attempt succeeds with probability about 1/2
+        .expect("ERR_VRF_ENCODE_TO_CURVE")
+}
+
+/// First 16 bytes of the hash of the five points.
+fn challenge(points: [&EdwardsPoint; 5]) -> [u8; 16] {
+    let hash = points
+        .iter()
+        .fold(Sha512::new().chain([SUITE, 0x02]), |hasher, point| {
+            hasher.chain(point.compress().as_bytes())
+        })
+        .chain([0x00])
+        .finalize();
+    hash[..16].try_into().unwrap()
+}
+
+fn proof_to_hash(gamma: &EdwardsPoint) -> [u8; 64] {
+    Sha512::new()
+        .chain([SUITE, 0x03])
+        .chain(gamma.mul_by_cofactor().compress().as_bytes())
+        .chain([0x00])
+        .finalize()
+        .into()
+}
+
+/// The VRF output (`beta`) for `alpha` if `proof` is valid for `public_key`.
+pub fn verify(public_key: &[u8], alpha: &[u8], proof: &[u8]) -> Option<[u8; 64]> {
+    let public_key: [u8; 32] = public_key.try_into().ok()?;
+    if proof.len() != PROOF_LEN {
+        return None;
+    }
+    let y = decode_point(&public_key).filter(|y| !y.is_small_order())?;
+    let gamma = decode_point(&proof[..32])?;
+    let c = scalar_from_challenge(&proof[32..48]);
+    let s = Scalar::from_canonical_bytes(proof[48..].try_into().unwrap())?;
+
+    let h = encode_to_curve(&public_key, alpha);
+    let u = EdwardsPoint::vartime_double_scalar_mul_basepoint(&-c, &y, &s);
+    let v = s * h - c * gamma;
+    (challenge([&y, &h, &gamma, &u, &v]) == proof[32..48]).then(|| proof_to_hash(&gamma))
+}
+
+/// Proves `alpha` with the ed25519 secret key `secret` (RFC 9381 section 5.1), returning
+/// the proof and its output.
+#[cfg(test)]
+pub fn prove(secret: &[u8; 32], alpha: &[u8]) -> (Vec<u8>, [u8; 64]) {
+    use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+
+    let expanded = Sha512::digest(secret);
+    let mut x_bytes: [u8; 32] = expanded[..32].try_into().unwrap();
+    x_bytes[0] &= 248;
+    x_bytes[31] &= 127;
+    x_bytes[31] |= 64;
+    let x = Scalar::from_bytes_mod_order(x_bytes);
+    let y = x * ED25519_BASEPOINT_POINT;
+
+    let h = encode_to_curve(y.compress().as_bytes(), alpha);
+    let gamma = x * h;
+    let k = Scalar::from_bytes_mod_order_wide(
+        &Sha512::new()
+            .chain(&expanded[32..])
+            .chain(h.compress().as_bytes())
+            .finalize()
+            .into(),
+    );
+    let c_bytes = challenge([&y, &h, &gamma, &(k * ED25519_BASEPOINT_POINT), &(k * h)]);
+    let s = k + scalar_from_challenge(&c_bytes) * x;
+    let proof = [
+        gamma.compress().as_bytes().as_slice(),
+        &c_bytes,
+        s.as_bytes(),
+    ]
+    .concat();
+    (proof, proof_to_hash(&gamma))
+}
@@ -53,10 +53,67 @@ test("Storage deposit", async (t) => {
 		{
 			url: "https://api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=usd",
 			data: JSON.stringify({ ethereum: { usd: 2000 } }),
+			callback: null,
+			encoding: null,
 		},
 		{
-			attachedDeposit: parseNEAR("0.000001 NEAR"),
+			attachedDeposit: parseNEAR("0.01 NEAR"),
 		}
 	);
 	console.log(JSON.parse(oracle_res.data));
 });
+
+test("Execute and await", async (t) => {
+	const { root, contract } = t.context.accounts;
+	await root.call(
+		contract.accountId,
+		"storage_deposit",
+		{},
+		{
+			attachedDeposit: parseNEAR("1 NEAR"),
+		}
+	);
+	await root.call(contract.accountId, "add_whitelisted", {
+		account_id: root.accountId,
+	});
+
+	const request: any = await root.call(
+		contract.accountId,
+		"create_oracle",
+		{
+			url: "https://api.coingecko.com/api/v3/simple/price?ids=near&vs_currencies=usd",
+			data: JSON.stringify({ path: "/near/usd" }),
+			callback: null,
+			encoding: null,
+		},
+		{
+			attachedDeposit: parseNEAR("0.01 NEAR"),
+		}
+	);
+	const executed: any = await root.call(contract.accountId, "execute_oracle", {
+		id: request.id,
+		return_value: "3.1",
+		attestation: null,
+		evidence: null,
+		raw_body: null,
+	});
+	t.true(executed.executed);
+	t.is(executed.return_value, "3.1");
+
+	// the awaiter is called back on `on_oracle_result`; only the deadline is checked here
+	const awaited: any = await root.call(
+		contract.accountId,
+		"create_oracle_await",
+		{
+			url: "https://api.coingecko.com/api/v3/simple/price?ids=near&vs_currencies=usd",
+			data: JSON.stringify({ path: "/near/usd" }),
+			callback: null,
+			timeout_ms: 1000,
+			encoding: null,
+		},
+		{
+			attachedDeposit: parseNEAR("0.01 NEAR"),
+		}
+	);
+	t.is(awaited.deadline, awaited.timestamp + 1000);
+});
@@ -0,0 +1,183 @@
+use crate::fetcher::RequestSpec;
+use crate::NodeError;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Kind of source a request reads, set by `source_type` in its spec.
+#[derive(Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceType {
+    /// Plain `GET` of the request URL.
+    #[default]
+    Http,
+    /// GraphQL `query` (and `variables`) posted to the request URL.
+    Graphql,
+    /// JSON-RPC 2.0 `method` (and `params`) posted to the request URL, e.g. another chain's
+    /// node.
+    Jsonrpc,
+}
+
+/// How a source is asked for data and how its response is unwrapped. The fetcher sends what
+/// the adapter builds, so rate limits, coalescing and secrets apply to every source type.
+pub trait Adapter: Sync {
+    /// JSON body to `POST` to the request URL, or `None` to `GET` it.
+    fn body(&self, spec: &RequestSpec) -> Result<Option<String>, NodeError>;
+    /// The document `path` selects from. A JSON string is returned unquoted.
+    fn document(&self, body: &str) -> Result<String, NodeError>;
+}
+
+pub struct HttpAdapter;
+
+pub struct GraphqlAdapter;
+
+pub struct JsonRpcAdapter;
+
+impl SourceType {
+    pub fn adapter(self) -> &'static dyn Adapter {
+        match self {
+            SourceType::Http => &HttpAdapter,
+            SourceType::Graphql => &GraphqlAdapter,
+            SourceType::Jsonrpc => &JsonRpcAdapter,
+        }
+    }
+}
+
+fn parse_json(body: &str) -> Result<Value, NodeError> {
+    serde_json::from_str(body)
+        .map_err(|err| NodeError::Fetch(format!("response is not JSON: {}", err)))
+}
+
+fn to_document(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+impl Adapter for HttpAdapter {
+    fn body(&self, _spec: &RequestSpec) -> Result<Option<String>, NodeError> {
+        Ok(None)
+    }
+
+    fn document(&self, body: &str) -> Result<String, NodeError> {
+        Ok(body.to_string())
+    }
+}
+
+impl Adapter for GraphqlAdapter {
+    fn body(&self, spec: &RequestSpec) -> Result<Option<String>, NodeError> {
+        let query = spec
+            .query
+            .as_ref()
+            .ok_or_else(|| NodeError::Fetch("graphql source without a query".to_string()))?;
+        let mut body = json!({ "query": query });
+        if let Some(variables) = &spec.variables {
+            body["variables"] = variables.clone();
+        }
+        Ok(Some(body.to_string()))
+    }
+
+    /// The response's `data`; any `errors` fail the fetch.
+    fn document(&self, body: &str) -> Result<String, NodeError> {
+        let response = parse_json(body)?;
+        match response.get("errors") {
+            Some(Value::Array(errors)) if !errors.is_empty() => {
+                return Err(NodeError::Fetch(format!("graphql errors: {}", errors[0])))
+            }
+            _ => {}
+        }
+        match response.get("data") {
+            Some(data) if !data.is_null() => Ok(to_document(data)),
+            _ => Err(NodeError::Fetch(
+                "graphql response without data".to_string(),
+            )),
+        }
+    }
+}
+
+impl Adapter for JsonRpcAdapter {
+    fn body(&self, spec: &RequestSpec) -> Result<Option<String>, NodeError> {
+        let method = spec
+            .method
+            .as_ref()
+            .ok_or_else(|| NodeError::Fetch("jsonrpc source without a method".to_string()))?;
+        Ok(Some(
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": spec.params.clone().unwrap_or_else(|| json!([])),
+            })
+            .to_string(),
+        ))
+    }
+
+    /// The response's `result`; an `error` fails the fetch.
+    fn document(&self, body: &str) -> Result<String, NodeError> {
+        let response = parse_json(body)?;
+        if let Some(error) = response.get("error").filter(|error| !error.is_null()) {
+            return Err(NodeError::Fetch(format!("jsonrpc error: {}", error)));
+        }
+        response
+            .get("result")
+            .map(to_document)
+            .ok_or_else(|| NodeError::Fetch("jsonrpc response without result".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_type_defaults_to_http() {
+        assert_eq!(
+            RequestSpec::parse("{}").unwrap().source_type,
+            SourceType::Http
+        );
+        let spec = RequestSpec::parse(r#"{"source_type": "jsonrpc", "method": "eth_blockNumber"}"#)
+            .unwrap();
+        assert_eq!(spec.source_type, SourceType::Jsonrpc);
+        assert_eq!(HttpAdapter.body(&spec).unwrap(), None);
+    }
+
+    #[test]
+    fn graphql_posts_query_and_unwraps_data() {
+        let spec = RequestSpec::parse(
+            r#"{"source_type": "graphql", "query": "query($id: ID!) { token(id: $id) { price } }", "variables": {"id": "near"}}"#,
+        )
+        .unwrap();
+        let body: Value =
+            serde_json::from_str(&GraphqlAdapter.body(&spec).unwrap().unwrap()).unwrap();
+        assert_eq!(body["variables"]["id"], "near");
+        assert_eq!(
+            GraphqlAdapter
+                .document(r#"{"data": {"token": {"price": 1.5}}}"#)
+                .unwrap(),
+            r#"{"token":{"price":1.5}}"#
+        );
+        assert!(GraphqlAdapter
+            .document(r#"{"data": null, "errors": [{"message": "unknown token"}]}"#)
+            .is_err());
+        assert!(GraphqlAdapter.body(&RequestSpec::default()).is_err());
+    }
+
+    #[test]
+    fn jsonrpc_posts_method_and_unwraps_result() {
+        let spec = RequestSpec::parse(r#"{"source_type": "jsonrpc", "method": "eth_blockNumber"}"#)
+            .unwrap();
+        let body: Value =
+            serde_json::from_str(&JsonRpcAdapter.body(&spec).unwrap().unwrap()).unwrap();
+        assert_eq!(body["method"], "eth_blockNumber");
+        assert_eq!(body["params"], json!([]));
+        assert_eq!(
+            JsonRpcAdapter
+                .document(r#"{"jsonrpc": "2.0", "id": 1, "result": "0x10"}"#)
+                .unwrap(),
+            "0x10"
+        );
+        assert!(JsonRpcAdapter
+            .document(r#"{"jsonrpc": "2.0", "id": 1, "error": {"code": -32601, "message": "no"}}"#)
+            .is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use crate::config::ChainConfig;
+use crate::signer::SignerKey;
+use crate::NodeError;
+use near_oracle_types::client::{FunctionCall, ViewCall};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::AccountId;
+use serde_json::{json, Value};
+use std::process::Command;
+
+/// How the node reads from and writes to the oracle contract.
+pub trait ChainClient: Sync {
+    /// Runs a view call, returning its raw (JSON) result.
+    fn view(&self, call: &ViewCall) -> Result<Vec<u8>, NodeError>;
+    /// Signs and sends a function call as the operator, waiting for its outcome.
+    fn call(&self, call: &FunctionCall) -> Result<CallOutcome, NodeError>;
+    /// Current nonce of `key`'s access key.
+    fn access_key_nonce(&self, key: &SignerKey) -> Result<u64, NodeError>;
+    /// Signs `call` with `key` at `nonce` and sends it without waiting for it to execute,
+    /// returning the transaction hash. A stale nonce gives [`NodeError::InvalidNonce`].
+    fn send(&self, call: &FunctionCall, key: &SignerKey, nonce: u64) -> Result<String, NodeError>;
+    /// Status of a transaction sent by `signer_id`.
+    fn tx_status(&self, tx_hash: &str, signer_id: &AccountId) -> Result<TxStatus, NodeError>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TxStatus {
+    /// Not seen by the RPC node: not propagated yet, or dropped.
+    Unknown,
+    /// Seen but not final yet.
+    Pending,
+    /// Executed successfully in a final block.
+    Final(CallOutcome),
+    /// Final and failed, with the failure.
+    Failed(String),
+}
+
+/// What is known about a successful function call.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct CallOutcome {
+    pub tx_hash: Option<String>,
+    /// Gas burnt by the transaction and its receipts, when it could be looked up.
+    pub gas_burnt: Option<u64>,
+}
+
+/// Views through NEAR JSON-RPC. [`call`](ChainClient::call) sends transactions through the
+/// `near` CLI, which holds the operator's keys; [`send`](ChainClient::send) signs them with
+/// the given key and broadcasts them over RPC.
+pub struct RpcChain {
+    config: ChainConfig,
+    agent: ureq::Agent,
+}
+
+impl RpcChain {
+    pub fn new(config: ChainConfig) -> Self {
+        Self {
+            config,
+            agent: ureq::Agent::new(),
+        }
+    }
+}
+
+impl RpcChain {
+    /// The whole JSON-RPC response, including any `error`.
+    fn rpc_response(&self, method: &str, params: Value) -> Result<Value, NodeError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": "oracle-node",
+            "method": method,
+            "params": params,
+        });
+        self.agent
+            .post(&self.config.rpc_url)
+            .send_json(request)
+            .map_err(|err| NodeError::Chain(err.to_string()))?
+            .into_json()
+            .map_err(|err| NodeError::Chain(err.to_string()))
+    }
+
+    fn rpc(&self, method: &str, params: Value) -> Result<Value, NodeError> {
+        let response = self.rpc_response(method, params)?;
+        if let Some(error) = response.get("error").or(response["result"].get("error")) {
+            return Err(rpc_error(error));
+        }
+        Ok(response["result"].clone())
+    }
+
+    /// Gas burnt by the transaction `tx_hash` and all of its receipts.
+    fn gas_burnt(&self, tx_hash: &str) -> Result<u64, NodeError> {
+        let result = self.rpc("tx", json!([tx_hash, self.config.operator_id]))?;
+        Ok(outcome_gas(&result))
+    }
+
+    fn final_block_hash(&self) -> Result<[u8; 32], NodeError> {
+        let block = self.rpc("block", json!({ "finality": "final" }))?;
+        block["header"]["hash"]
+            .as_str()
+            .and_then(|hash| bs58::decode(hash).into_vec().ok())
+            .and_then(|hash| hash.try_into().ok())
+            .ok_or_else(|| NodeError::Chain("block without hash".to_string()))
+    }
+}
+
+fn rpc_error(error: &Value) -> NodeError {
+    match find_key(error, "InvalidNonce").and_then(|nonce| nonce["ak_nonce"].as_u64()) {
+        Some(ak_nonce) => NodeError::InvalidNonce { ak_nonce },
+        None => NodeError::Chain(error.to_string()),
+    }
+}
+
+/// The first value under `key` anywhere in `value`.
+fn find_key<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(map) => map
+            .get(key)
+            .or_else(|| map.values().find_map(|value| find_key(value, key))),
+        Value::Array(values) => values.iter().find_map(|value| find_key(value, key)),
+        _ => None,
+    }
+}
+
+fn outcome_gas(result: &Value) -> u64 {
+    std::iter::once(&result["transaction_outcome"])
+        .chain(result["receipts_outcome"].as_array().into_iter().flatten())
+        .filter_map(|outcome| outcome["outcome"]["gas_burnt"].as_u64())
+        .sum()
+}
+
+/// Status of a `tx` RPC result; only `FINAL` outcomes are reported as settled.
+fn parse_tx_status(tx_hash: &str, result: &Value) -> TxStatus {
+    if result["final_execution_status"] != "FINAL" {
+        return TxStatus::Pending;
+    }
+    match result["status"].get("Failure") {
+        Some(failure) => TxStatus::Failed(failure.to_string()),
+        None => TxStatus::Final(CallOutcome {
+            tx_hash: Some(tx_hash.to_string()),
+            gas_burnt: Some(outcome_gas(result)),
+        }),
+    }
+}
+
+/// The hash in the `Transaction Id <hash>` line the `near` CLI prints.
+fn parse_tx_hash(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("Transaction Id ")
+            .map(|hash| hash.trim().to_string())
+    })
+}
+
+impl ChainClient for RpcChain {
+    fn view(&self, call: &ViewCall) -> Result<Vec<u8>, NodeError> {
+        let result = self.rpc(
+            "query",
+            json!({
+                "request_type": "call_function",
+                "finality": "final",
+                "account_id": call.contract_id,
+                "method_name": call.method_name,
+                "args_base64": Base64VecU8(call.args.clone()),
+            }),
+        )?;
+        serde_json::from_value(result["result"].clone())
+            .map_err(|err| NodeError::Chain(err.to_string()))
+    }
+
+    fn call(&self, call: &FunctionCall) -> Result<CallOutcome, NodeError> {
+        let output = Command::new(&self.config.near_cli)
+            .arg("call")
+            .arg(call.receiver_id.as_str())
+            .arg(&call.method_name)
+            .arg(String::from_utf8_lossy(&call.args).as_ref())
+            .args(["--accountId", self.config.operator_id.as_str()])
+            .args(["--gas", &call.gas.to_string()])
+            .args(["--depositYocto", &call.deposit.to_string()])
+            .output()
+            .map_err(|err| NodeError::Chain(format!("{}: {}", self.config.near_cli, err)))?;
+        if !output.status.success() {
+            return Err(NodeError::Chain(
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            ));
+        }
+        let tx_hash = parse_tx_hash(&String::from_utf8_lossy(&output.stdout));
+        let gas_burnt = tx_hash
+            .as_deref()
+            .and_then(|hash| match self.gas_burnt(hash) {
+                Ok(gas) => Some(gas),
+                Err(err) => {
+                    log::warn!("tx {}: {}", hash, err);
+                    None
+                }
+            });
+        Ok(CallOutcome { tx_hash, gas_burnt })
+    }
+
+    fn access_key_nonce(&self, key: &SignerKey) -> Result<u64, NodeError> {
+        let result = self.rpc(
+            "query",
+            json!({
+                "request_type": "view_access_key",
+                "finality": "final",
+                "account_id": key.account_id,
+                "public_key": key.public_key(),
+            }),
+        )?;
+        result["nonce"]
+            .as_u64()
+            .ok_or_else(|| NodeError::Chain(format!("access key without nonce: {}", result)))
+    }
+
+    fn send(&self, call: &FunctionCall, key: &SignerKey, nonce: u64) -> Result<String, NodeError> {
+        let signed = key.sign(call, nonce, self.final_block_hash()?);
+        self.rpc(
+            "send_tx",
+            json!({
+                "signed_tx_base64": Base64VecU8(signed.bytes),
+                "wait_until": "NONE",
+            }),
+        )?;
+        Ok(signed.hash)
+    }
+
+    fn tx_status(&self, tx_hash: &str, signer_id: &AccountId) -> Result<TxStatus, NodeError> {
+        let response = self.rpc_response(
+            "tx",
+            json!({
+                "tx_hash": tx_hash,
+                "sender_account_id": signer_id,
+                "wait_until": "NONE",
+            }),
+        )?;
+        match response.get("error") {
+            Some(error) if error.to_string().contains("UNKNOWN_TRANSACTION") => {
+                Ok(TxStatus::Unknown)
+            }
+            Some(error) => Err(rpc_error(error)),
+            None => Ok(parse_tx_status(tx_hash, &response["result"])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cli_transaction_id() {
+        let output = "Scheduling a call: oracle.testnet.execute_oracle_batch(...)\n\
+                      Doing account.functionCall()\n\
+                      Transaction Id 9X1H8mB6rZ4hNkHd\n\
+                      To see the transaction in the transaction explorer, please open this url\n";
+        assert_eq!(parse_tx_hash(output).as_deref(), Some("9X1H8mB6rZ4hNkHd"));
+        assert_eq!(parse_tx_hash(""), None);
+    }
+
+    #[test]
+    fn recognises_invalid_nonce() {
+        let error = json!({
+            "name": "HANDLER_ERROR",
+            "cause": {"name": "INVALID_TRANSACTION"},
+            "data": {"TxExecutionError": {"InvalidTxError": {
+                "InvalidNonce": {"tx_nonce": 5, "ak_nonce": 7}
+            }}},
+        });
+        assert!(matches!(
+            rpc_error(&error),
+            NodeError::InvalidNonce { ak_nonce: 7 }
+        ));
+        assert!(matches!(
+            rpc_error(&json!({"name": "TIMEOUT_ERROR"})),
+            NodeError::Chain(_)
+        ));
+    }
+
+    #[test]
+    fn reports_only_final_outcomes() {
+        let outcome = |status: &str, result: Value| {
+            json!({
+                "final_execution_status": status,
+                "status": result,
+                "transaction_outcome": {"outcome": {"gas_burnt": 2}},
+                "receipts_outcome": [{"outcome": {"gas_burnt": 3}}],
+            })
+        };
+        assert_eq!(
+            parse_tx_status(
+                "h",
+                &outcome("EXECUTED_OPTIMISTIC", json!({"SuccessValue": ""}))
+            ),
+            TxStatus::Pending
+        );
+        assert_eq!(
+            parse_tx_status("h", &outcome("FINAL", json!({"SuccessValue": ""}))),
+            TxStatus::Final(CallOutcome {
+                tx_hash: Some("h".to_string()),
+                gas_burnt: Some(5),
+            })
+        );
+        assert!(matches!(
+            parse_tx_status(
+                "h",
+                &outcome("FINAL", json!({"Failure": {"ActionError": {}}}))
+            ),
+            TxStatus::Failed(_)
+        ));
+    }
+}
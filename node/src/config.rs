@@ -0,0 +1,277 @@
+use crate::NodeError;
+use near_oracle_types::client::MAX_CALL_GAS;
+use near_sdk::AccountId;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Node configuration, read from TOML:
+///
+/// ```toml
+/// [chain]
+/// contract_id = "oracle.testnet"
+/// operator_id = "operator.testnet"
+///
+/// [poll]
+/// interval_ms = 2000
+/// ```
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub chain: ChainConfig,
+    #[serde(default)]
+    pub poll: PollConfig,
+    #[serde(default)]
+    pub fetch: FetchConfig,
+    #[serde(default)]
+    pub batch: BatchConfig,
+    #[serde(default)]
+    pub store: StoreConfig,
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub submitter: SubmitterConfig,
+    #[serde(default)]
+    pub stream: StreamConfig,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ChainConfig {
+    pub contract_id: AccountId,
+    /// Whitelisted account the results are submitted from.
+    pub operator_id: AccountId,
+    #[serde(default = "default_rpc_url")]
+    pub rpc_url: String,
+    /// `near` CLI used by [`RpcChain`](crate::RpcChain) to sign and send transactions.
+    #[serde(default = "default_near_cli")]
+    pub near_cli: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct PollConfig {
+    pub interval_ms: u64,
+    /// `limit` of each `get_queued_data` page.
+    pub page_size: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct FetchConfig {
+    pub timeout_ms: u64,
+    /// Requests for the same URL within this window share one response.
+    pub coalesce_window_ms: u64,
+    /// Back-off after a 429 or 503 response without a usable `Retry-After`.
+    pub default_retry_after_ms: u64,
+    /// Longest back-off a server's `Retry-After` may ask for.
+    pub max_retry_after_ms: u64,
+    /// Per-host limits, e.g. `[fetch.hosts."api.coingecko.com"]`. Other hosts are not
+    /// limited.
+    pub hosts: HashMap<String, HostLimit>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct HostLimit {
+    pub rate_per_sec: f64,
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatchConfig {
+    /// Most results submitted in one `execute_oracle_batch` call.
+    pub max_requests: usize,
+    /// Gas for executing one result, on top of its callback's gas.
+    pub gas_per_request: u64,
+    /// Most gas attached to one batch.
+    pub gas_budget: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoreConfig {
+    /// Job database directory; without one jobs are kept in memory only.
+    pub path: Option<PathBuf>,
+    /// Age after which confirmed jobs, and jobs of requests no longer queued, are dropped.
+    pub retention_ms: u64,
+    /// Time after which a submission that did not land is sent again. A submitted request
+    /// is never resubmitted while it may still be in flight.
+    pub resubmit_after_ms: u64,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecretsConfig {
+    /// Secrets file, see [`Secrets`](crate::Secrets). Kept apart from this config so the
+    /// config can be shared without the keys.
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct StreamConfig {
+    /// JSON lines of final blocks with their receipt logs, appended by an indexer (see
+    /// [`JsonlStream`](crate::stream::JsonlStream)). With it the queue is followed from the
+    /// contract's events, and read in full only at start and after a gap; without, it is
+    /// read in full every poll.
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// Address serving `/metrics` and `/healthz`, e.g. `127.0.0.1:9100`; off when unset.
+    pub listen: Option<String>,
+    /// `/healthz` fails once the last successful poll, or the oldest request left
+    /// unfulfilled, is older than this.
+    pub max_lag_ms: u64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct SubmitterConfig {
+    /// `near` CLI credential files of `operator_id` access keys. With keys, batches are
+    /// signed by the node and sent in parallel, one per key, and tracked to finality;
+    /// without, each batch goes through `near_cli`.
+    pub key_files: Vec<PathBuf>,
+    /// Resends with a refreshed nonce after an invalid nonce error.
+    pub nonce_retries: u32,
+}
+
+fn default_burst() -> u32 {
+    1
+}
+
+fn default_rpc_url() -> String {
+    "https://rpc.testnet.near.org".to_string()
+}
+
+fn default_near_cli() -> String {
+    "near".to_string()
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 2_000,
+            page_size: 50,
+        }
+    }
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 10_000,
+            coalesce_window_ms: 5_000,
+            default_retry_after_ms: 60_000,
+            max_retry_after_ms: 3_600_000,
+            hosts: HashMap::new(),
+        }
+    }
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_requests: 10,
+            gas_per_request: 15_000_000_000_000,
+            gas_budget: MAX_CALL_GAS,
+        }
+    }
+}
+
+impl Default for SubmitterConfig {
+    fn default() -> Self {
+        Self {
+            key_files: vec![],
+            nonce_retries: 3,
+        }
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            max_lag_ms: 60_000,
+        }
+    }
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            retention_ms: 24 * 60 * 60 * 1000,
+            resubmit_after_ms: 10 * 60 * 1000,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_toml(source: &str) -> Result<Self, NodeError> {
+        toml::from_str(source).map_err(|err| NodeError::Config(err.to_string()))
+    }
+    pub fn load(path: &Path) -> Result<Self, NodeError> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|err| NodeError::Config(format!("{}: {}", path.display(), err)))?;
+        Self::from_toml(&source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_minimal_config_with_defaults() {
+        let config = Config::from_toml(
+            r#"
+            [chain]
+            contract_id = "oracle.testnet"
+            operator_id = "operator.testnet"
+
+            [batch]
+            max_requests = 4
+
+            [fetch.hosts."api.coingecko.com"]
+            rate_per_sec = 0.5
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.chain.contract_id.as_str(), "oracle.testnet");
+        assert_eq!(config.chain.rpc_url, "https://rpc.testnet.near.org");
+        assert_eq!(config.poll.page_size, 50);
+        assert_eq!(config.batch.max_requests, 4);
+        assert_eq!(config.batch.gas_per_request, 15_000_000_000_000);
+        assert_eq!(config.fetch.hosts["api.coingecko.com"].burst, 1);
+    }
+
+    #[test]
+    fn example_config_needs_no_local_files() {
+        let config = Config::from_toml(include_str!("../oracle-node.example.toml")).unwrap();
+        assert!(config.secrets.path.is_none());
+        assert!(config.submitter.key_files.is_empty());
+        assert!(config.stream.path.is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let err = Config::from_toml(
+            r#"
+            [chain]
+            contract_id = "oracle.testnet"
+            operator_id = "operator.testnet"
+            contract = "typo.testnet"
+            "#,
+        )
+        .unwrap_err();
+        assert!(matches!(err, NodeError::Config(_)));
+    }
+}
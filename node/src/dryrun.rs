@@ -0,0 +1,168 @@
+use crate::chain::ChainClient;
+use crate::node::{is_pending, now_ms, Node};
+use crate::submitter::{pack, Fulfilment};
+use crate::NodeError;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// One line of a dry-run report: what the node would submit for a request, and how that
+/// compares to the value already on chain.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DryRunEntry {
+    pub id: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Why no value could be fetched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Value another operator already submitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submitted: Option<String>,
+    /// Whether `value` equals `submitted`, when both are known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches: Option<bool>,
+    /// `value - submitted` when both are numbers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta: Option<f64>,
+    /// Index of the batch the value would be sent in, for unexecuted requests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch: Option<usize>,
+    /// Gas that batch would attach.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_gas: Option<u64>,
+}
+
+/// Counts from a dry run.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DryRunReport {
+    /// Unexecuted requests whose value would be submitted.
+    pub would_submit: usize,
+    pub batches: usize,
+    pub fetch_failed: usize,
+    /// Executed requests whose value matches the one on chain.
+    pub matched: usize,
+    pub mismatched: usize,
+}
+
+impl DryRunEntry {
+    fn new(
+        id: &str,
+        url: &str,
+        value: Result<String, NodeError>,
+        submitted: Option<String>,
+    ) -> Self {
+        let (value, error) = match value {
+            Ok(value) => (Some(value), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        let (matches, delta) = match (&value, &submitted) {
+            (Some(value), Some(submitted)) => {
+                (Some(value == submitted), numeric_delta(value, submitted))
+            }
+            _ => (None, None),
+        };
+        Self {
+            id: id.to_string(),
+            url: url.to_string(),
+            value,
+            error,
+            submitted,
+            matches,
+            delta,
+            batch: None,
+            batch_gas: None,
+        }
+    }
+}
+
+fn numeric_delta(value: &str, submitted: &str) -> Option<f64> {
+    Some(value.trim().parse::<f64>().ok()? - submitted.trim().parse::<f64>().ok()?)
+}
+
+/// Reads the queue, fetches (and transforms) every pending request and every request
+/// another operator already executed, and writes a JSON line per request to `out`. Nothing
+/// is sent and the job store is left untouched, so it is safe to run against mainnet.
+pub fn dry_run<C: ChainClient>(
+    node: &Node<C>,
+    out: &mut impl Write,
+) -> Result<DryRunReport, NodeError> {
+    let now = now_ms();
+    let config = &node.config().batch;
+    let mut report = DryRunReport::default();
+    let mut entries = vec![];
+    let mut fulfilments = vec![];
+    for request in node.queue()? {
+        let submitted = match &request.return_value {
+            Some(value) if request.executed => Some(value.clone()),
+            _ if is_pending(&request, now) => None,
+            _ => continue,
+        };
+        let entry = DryRunEntry::new(&request.id, &request.url, node.fetch(&request), submitted);
+        match (&entry.value, entry.matches) {
+            (None, _) => report.fetch_failed += 1,
+            (Some(_), Some(true)) => report.matched += 1,
+            (Some(_), Some(false)) => report.mismatched += 1,
+            (Some(value), None) => {
+                report.would_submit += 1;
+                fulfilments.push(Fulfilment {
+                    id: request.id.clone(),
+                    value: value.clone(),
+                    gas: config.gas_per_request
+                        + request
+                            .callback
+                            .as_ref()
+                            .map_or(0, |callback| callback.gas.0),
+                });
+            }
+        }
+        entries.push(entry);
+    }
+
+    let batches = pack(fulfilments, config);
+    report.batches = batches.len();
+    let batch_of: HashMap<String, (usize, u64)> = batches
+        .iter()
+        .enumerate()
+        .flat_map(|(index, batch)| {
+            batch
+                .ids()
+                .into_iter()
+                .map(move |id| (id, (index, batch.gas)))
+        })
+        .collect();
+    for mut entry in entries {
+        if let Some((index, gas)) = batch_of.get(&entry.id) {
+            entry.batch = Some(*index);
+            entry.batch_gas = Some(*gas);
+        }
+        serde_json::to_writer(&mut *out, &entry)
+            .map_err(|err| NodeError::Store(err.to_string()))?;
+        writeln!(out).map_err(|err| NodeError::Store(err.to_string()))?;
+    }
+    out.flush()
+        .map_err(|err| NodeError::Store(err.to_string()))?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_with_submitted_value() {
+        let entry = DryRunEntry::new("a", "u", Ok("3.10".to_string()), Some("3.05".to_string()));
+        assert_eq!(entry.matches, Some(false));
+        assert!((entry.delta.unwrap() - 0.05).abs() < 1e-9);
+
+        let entry = DryRunEntry::new("a", "u", Ok("up".to_string()), Some("up".to_string()));
+        assert_eq!((entry.matches, entry.delta), (Some(true), None));
+
+        let entry = DryRunEntry::new("a", "u", Err(NodeError::Fetch("500".to_string())), None);
+        assert_eq!(
+            serde_json::to_string(&entry).unwrap(),
+            r#"{"id":"a","url":"u","error":"fetch: 500"}"#
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use crate::adapter::SourceType;
+use crate::config::FetchConfig;
+use crate::metrics::Metrics;
+use crate::ratelimit::{parse_retry_after, TokenBucket};
+use crate::secrets::Secrets;
+use crate::NodeError;
+use near_oracle_types::OracleData;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// What the node reads out of a response, given in a request's `data`. A request without a
+/// `path` is answered with the whole response body, or the whole GraphQL `data` or JSON-RPC
+/// `result`.
+///
+/// The request URL and header values may hold `{{secret:<name>}}` placeholders, filled in
+/// from the node's [`Secrets`] for the hosts each secret allows.
+#[derive(Deserialize, Default, Debug)]
+pub struct RequestSpec {
+    /// JSON pointer (e.g. `/near/usd`) into a JSON response.
+    pub path: Option<String>,
+    /// Extra request headers, e.g. `{"x-api-key": "{{secret:api_key}}"}`.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Adapter the source is read with; plain HTTP `GET` by default.
+    #[serde(default)]
+    pub source_type: SourceType,
+    /// GraphQL query and its variables.
+    pub query: Option<String>,
+    pub variables: Option<Value>,
+    /// JSON-RPC method and its params (`[]` when unset).
+    pub method: Option<String>,
+    pub params: Option<Value>,
+}
+
+impl RequestSpec {
+    /// `data` that is not a JSON object gives the default spec. An object is read as a spec,
+    /// ignoring fields a spec does not have (e.g. the `{}` placeholder or the baseline's
+    /// `{"id": ..}` payloads), so a mistyped field or an unknown `source_type` fails here
+    /// instead of the request being answered with the whole response body.
+    pub fn parse(data: &str) -> Result<Self, NodeError> {
+        match serde_json::from_str::<Value>(data) {
+            Ok(spec @ Value::Object(_)) => serde_json::from_value(spec)
+                .map_err(|err| NodeError::Fetch(format!("invalid request data: {}", err))),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// The return value for a response `body`. String leaves are submitted unquoted, other
+    /// JSON values in their JSON form.
+    pub fn extract(&self, body: &str) -> Result<String, NodeError> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(body.trim().to_string()),
+        };
+        let json: Value = serde_json::from_str(body)
+            .map_err(|err| NodeError::Fetch(format!("response is not JSON: {}", err)))?;
+        match json.pointer(path) {
+            Some(Value::String(value)) => Ok(value.clone()),
+            Some(value) => Ok(value.to_string()),
+            None => Err(NodeError::Fetch(format!("{} not found in response", path))),
+        }
+    }
+}
+
+struct HostState {
+    bucket: Option<TokenBucket>,
+    blocked_until: Option<Instant>,
+}
+
+/// Fetches request URLs, sharing responses for identical URLs within
+/// `coalesce_window_ms`, applying per-host token buckets and backing off a host after a
+/// 429 or 503 response. Secret values never appear in its errors or logs, and redirects are
+/// not followed, as they would carry resolved secret headers to another host.
+pub struct HttpFetcher {
+    agent: ureq::Agent,
+    config: FetchConfig,
+    secrets: Secrets,
+    metrics: Arc<Metrics>,
+    responses: Mutex<HashMap<String, (Instant, String)>>,
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+impl HttpFetcher {
+    pub fn new(config: &FetchConfig, secrets: Secrets, metrics: Arc<Metrics>) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_millis(config.timeout_ms))
+                .redirects(0)
+                .build(),
+            config: config.clone(),
+            secrets,
+            metrics,
+            responses: Mutex::default(),
+            hosts: Mutex::default(),
+        }
+    }
+
+    /// Fetches the request's URL and extracts its return value. A value that contains a
+    /// secret is refused rather than published on chain.
+    pub fn fetch(&self, request: &OracleData) -> Result<String, NodeError> {
+        self.fetch_with(request, |spec, document| spec.extract(document))
+    }
+
+    /// Like [`fetch`](Self::fetch), with `extract` turning the response document into the
+    /// value.
+    pub fn fetch_with(
+        &self,
+        request: &OracleData,
+        extract: impl FnOnce(&RequestSpec, &str) -> Result<String, NodeError>,
+    ) -> Result<String, NodeError> {
+        let spec = RequestSpec::parse(&request.data)?;
+        let adapter = spec.source_type.adapter();
+        let value = adapter
+            .body(&spec)
+            .and_then(|body| self.fetch_body(&request.url, &spec.headers, body.as_deref()))
+            .and_then(|body| adapter.document(&body))
+            .and_then(|document| extract(&spec, &document))
+            .map_err(|err| self.secrets.redact_error(err))?;
+        if self.secrets.leaks(&value) {
+            return Err(NodeError::Fetch(format!(
+                "{}: response contains a secret",
+                request.url
+            )));
+        }
+        Ok(value)
+    }
+
+    /// `GET`s `url`, or `POST`s `body` as JSON to it. `url` and `headers` are templates;
+    /// responses are shared by template, so resolved secrets are not kept as cache keys.
+    fn fetch_body(
+        &self,
+        url: &str,
+        headers: &BTreeMap<String, String>,
+        body: Option<&str>,
+    ) -> Result<String, NodeError> {
+        let now = Instant::now();
+        let window = Duration::from_millis(self.config.coalesce_window_ms);
+        let key = format!("{} {:?} {:?}", url, headers, body);
+        if let Some((fetched_at, body)) = self.responses.lock().unwrap().get(&key) {
+            if now.saturating_duration_since(*fetched_at) < window {
+                return Ok(body.clone());
+            }
+        }
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .ok_or_else(|| NodeError::Fetch(format!("{}: invalid url", url)))?;
+        let resolved = self.secrets.resolve(url, &host)?;
+        let mut request = match body {
+            Some(_) => self
+                .agent
+                .post(&resolved)
+                .set("Content-Type", "application/json"),
+            None => self.agent.get(&resolved),
+        };
+        for (name, value) in headers {
+            request = request.set(name, &self.secrets.resolve(value, &host)?);
+        }
+        self.acquire(&host, now)?;
+
+        let started = Instant::now();
+        let response = match body {
+            Some(body) => request.send_string(body),
+            None => request.call(),
+        };
+        self.metrics.observe_fetch(&host, started.elapsed());
+        let body = match response {
+            Ok(response) if (300..400).contains(&response.status()) => {
+                return Err(NodeError::Fetch(format!(
+                    "{}: {} redirect to {} not followed",
+                    url,
+                    response.status(),
+                    response.header("Location").unwrap_or("nowhere")
+                )));
+            }
+            Ok(response) => response
+                .into_string()
+                .map_err(|err| NodeError::Fetch(format!("{}: {}", url, err)))?,
+            Err(ureq::Error::Status(status @ (429 | 503), response)) => {
+                let max_retry_after = Duration::from_millis(self.config.max_retry_after_ms);
+                let retry_after =
+                    parse_retry_after(response.header("Retry-After"), max_retry_after)
+                        .unwrap_or(Duration::from_millis(self.config.default_retry_after_ms));
+                self.host_state(&mut self.hosts.lock().unwrap(), &host, now)
+                    .blocked_until = now.checked_add(retry_after);
+                log::info!("{}: {}, backing off for {:?}", url, status, retry_after);
+                return Err(NodeError::RateLimited {
+                    host,
+                    retry_after_ms: retry_after.as_millis() as u64,
+                });
+            }
+            Err(err) => return Err(NodeError::Fetch(format!("{}: {}", url, err))),
+        };
+
+        let mut responses = self.responses.lock().unwrap();
+        responses.retain(|_, (fetched_at, _)| now.saturating_duration_since(*fetched_at) < window);
+        responses.insert(key, (now, body.clone()));
+        Ok(body)
+    }
+
+    fn host_state<'a>(
+        &self,
+        hosts: &'a mut HashMap<String, HostState>,
+        host: &str,
+        now: Instant,
+    ) -> &'a mut HostState {
+        hosts.entry(host.to_string()).or_insert_with(|| HostState {
+            bucket: self
+                .config
+                .hosts
+                .get(host)
+                .map(|limit| TokenBucket::new(limit, now)),
+            blocked_until: None,
+        })
+    }
+
+    /// Takes a token for `host`, unless it is backing off or out of tokens.
+    fn acquire(&self, host: &str, now: Instant) -> Result<(), NodeError> {
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = self.host_state(&mut hosts, host, now);
+        let wait = match state.blocked_until {
+            Some(until) if until > now => Err(until - now),
+            _ => state
+                .bucket
+                .as_mut()
+                .map_or(Ok(()), |bucket| bucket.try_take(now)),
+        };
+        wait.map_err(|wait| NodeError::RateLimited {
+            host: host.to_string(),
+            retry_after_ms: wait.as_millis().min(u64::MAX as u128) as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_json_pointer_or_whole_body() {
+        let body = r#"{"near": {"usd": 3.1}, "name": "near"}"#;
+        assert_eq!(
+            RequestSpec::parse(r#"{"path": "/near/usd"}"#)
+                .unwrap()
+                .extract(body)
+                .unwrap(),
+            "3.1"
+        );
+        assert_eq!(
+            RequestSpec::parse(r#"{"path": "/name"}"#)
+                .unwrap()
+                .extract(body)
+                .unwrap(),
+            "near"
+        );
+        assert_eq!(
+            RequestSpec::parse("{}").unwrap().extract(" 42\n").unwrap(),
+            "42"
+        );
+        assert!(RequestSpec::parse(r#"{"path": "/btc"}"#)
+            .unwrap()
+            .extract(body)
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_specs_it_cannot_read() {
+        assert!(RequestSpec::parse(r#"{"source_type": "websocket"}"#).is_err());
+        assert!(RequestSpec::parse(r#"{"headers": ["x-api-key"]}"#).is_err());
+        // data that is not an object, or an object of other fields, is a plain GET
+        assert_eq!(
+            RequestSpec::parse(r#""near""#).unwrap().source_type,
+            SourceType::Http
+        );
+        assert!(RequestSpec::parse(r#"{"id": "bitcoin"}"#)
+            .unwrap()
+            .path
+            .is_none());
+    }
+}
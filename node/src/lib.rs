@@ -0,0 +1,72 @@
+//! Reference operator node: polls the oracle contract for unexecuted requests, fetches their
+//! URLs and submits the results in `execute_oracle_batch` calls.
+
+use std::fmt;
+
+pub mod adapter;
+pub mod chain;
+pub mod config;
+pub mod dryrun;
+pub mod fetcher;
+pub mod metrics;
+pub mod mock;
+pub mod node;
+pub mod ratelimit;
+pub mod secrets;
+pub mod signer;
+pub mod store;
+pub mod stream;
+pub mod submitter;
+pub mod transform;
+
+pub use adapter::{Adapter, SourceType};
+pub use chain::{CallOutcome, ChainClient, RpcChain, TxStatus};
+pub use config::Config;
+pub use dryrun::{dry_run, DryRunEntry, DryRunReport};
+pub use fetcher::HttpFetcher;
+pub use metrics::Metrics;
+pub use mock::{MockChain, MockStream};
+pub use node::{Node, PollReport};
+pub use secrets::Secrets;
+pub use signer::SignerKey;
+pub use store::{Job, JobStore, Stage};
+pub use stream::{BlockStream, JsonlStream, StreamBlock};
+pub use submitter::Submitter;
+pub use transform::Transforms;
+
+#[derive(Debug)]
+pub enum NodeError {
+    Config(String),
+    Chain(String),
+    Fetch(String),
+    /// The host may not be fetched for `retry_after_ms`.
+    RateLimited {
+        host: String,
+        retry_after_ms: u64,
+    },
+    Store(String),
+    /// A transaction was rejected because its nonce is not above the access key's.
+    InvalidNonce {
+        ak_nonce: u64,
+    },
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeError::Config(err) => write!(f, "config: {}", err),
+            NodeError::Chain(err) => write!(f, "chain: {}", err),
+            NodeError::Fetch(err) => write!(f, "fetch: {}", err),
+            NodeError::RateLimited {
+                host,
+                retry_after_ms,
+            } => write!(f, "{} rate limited for {}ms", host, retry_after_ms),
+            NodeError::Store(err) => write!(f, "store: {}", err),
+            NodeError::InvalidNonce { ak_nonce } => {
+                write!(f, "invalid nonce, access key nonce is {}", ak_nonce)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NodeError {}
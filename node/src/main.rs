@@ -0,0 +1,109 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use oracle_node::{dry_run, metrics, Config, Node, RpcChain};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::AtomicBool;
+
+const USAGE: &str = "usage: oracle-node [--dry-run <report.jsonl>] <config.toml>";
+
+/// Writes the node's log to stderr, at the level `ORACLE_NODE_LOG` names (`info` by default).
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{} {}", record.level(), record.args());
+        }
+    }
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+fn main() -> ExitCode {
+    let level = std::env::var("ORACLE_NODE_LOG")
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(LevelFilter::Info);
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+    let mut args = std::env::args_os().skip(1);
+    let mut report = None;
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        match (arg.to_str(), &path) {
+            (Some("--dry-run"), _) => match args.next() {
+                Some(report_path) => report = Some(PathBuf::from(report_path)),
+                None => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::FAILURE;
+                }
+            },
+            (_, None) => path = Some(PathBuf::from(arg)),
+            (_, Some(_)) => {
+                eprintln!("{}", USAGE);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    let path = match path {
+        Some(path) => path,
+        None => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut config = match Config::load(&path) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    if report.is_some() {
+        // never touch the live node's job database or keys
+        config.store.path = None;
+        config.submitter.key_files.clear();
+    }
+    let node = match Node::new(config.clone(), RpcChain::new(config.chain)) {
+        Ok(node) => node,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(report) = report {
+        let result = File::create(&report)
+            .map_err(|err| format!("{}: {}", report.display(), err))
+            .and_then(|file| {
+                dry_run(&node, &mut BufWriter::new(file)).map_err(|err| err.to_string())
+            });
+        return match result {
+            Ok(summary) => {
+                log::info!("{:?}", summary);
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if let Some(listen) = &config.metrics.listen {
+        match metrics::serve(listen, node.metrics().clone()) {
+            Ok(addr) => log::info!("serving /metrics and /healthz on {}", addr),
+            Err(err) => {
+                eprintln!("{}", err);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    node.run(&AtomicBool::new(false));
+    ExitCode::SUCCESS
+}
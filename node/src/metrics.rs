@@ -0,0 +1,308 @@
+use crate::NodeError;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const FETCH_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+const LAG_BUCKETS: &[f64] = &[1.0, 2.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+/// Hosts with their own fetch latency series; later hosts share [`OTHER_HOST`], as request
+/// URLs are chosen by anyone creating a request.
+const MAX_HOST_LABELS: usize = 32;
+const OTHER_HOST: &str = "other";
+
+#[derive(Clone, Debug)]
+struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.bounds.iter().zip(&mut self.counts) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// Writes the histogram's series; `labels` is empty or ends with a comma.
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{}le=\"{}\"}} {}",
+                name, labels, bound, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}le=\"+Inf\"}} {}",
+            name, labels, self.count
+        );
+        let labels = match labels.trim_end_matches(',') {
+            "" => String::new(),
+            labels => format!("{{{}}}", labels),
+        };
+        let _ = writeln!(out, "{}_sum{} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{} {}", name, labels, self.count);
+    }
+}
+
+#[derive(Default)]
+struct State {
+    last_poll_ms: Option<u64>,
+    queue_depth: usize,
+    unfetchable: usize,
+    oldest_unfulfilled_ms: Option<u64>,
+    fetch_seconds: BTreeMap<String, Histogram>,
+    batches_succeeded: u64,
+    batches_failed: u64,
+    requests_submitted: u64,
+    gas_burnt: u64,
+    lag_seconds: Option<Histogram>,
+}
+
+/// Operator metrics, rendered in the Prometheus text format by [`serve`], which also answers
+/// `/healthz`. The node is healthy while it polls and fulfils requests within `max_lag_ms`.
+pub struct Metrics {
+    max_lag_ms: u64,
+    state: Mutex<State>,
+}
+
+impl Metrics {
+    pub fn new(max_lag_ms: u64) -> Self {
+        Self {
+            max_lag_ms,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Records a successful poll that saw `queue_depth` pending requests, `unfetchable` of
+    /// which could not be fetched. The oldest of the others, if not fulfilled during the poll,
+    /// was created at `oldest_unfulfilled_ms`.
+    pub fn observe_poll(
+        &self,
+        now: u64,
+        queue_depth: usize,
+        unfetchable: usize,
+        oldest_unfulfilled_ms: Option<u64>,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state.last_poll_ms = Some(now);
+        state.queue_depth = queue_depth;
+        state.unfetchable = unfetchable;
+        state.oldest_unfulfilled_ms = oldest_unfulfilled_ms;
+    }
+
+    pub fn observe_fetch(&self, host: &str, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        let host = match state.fetch_seconds.contains_key(host)
+            || state.fetch_seconds.len() < MAX_HOST_LABELS
+        {
+            true => host,
+            false => OTHER_HOST,
+        };
+        state
+            .fetch_seconds
+            .entry(host.to_string())
+            .or_insert_with(|| Histogram::new(FETCH_BUCKETS))
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Records an `execute_oracle_batch` call of `requests` results.
+    pub fn observe_batch(&self, succeeded: bool, requests: usize, gas_burnt: Option<u64>) {
+        let mut state = self.state.lock().unwrap();
+        if succeeded {
+            state.batches_succeeded += 1;
+            state.requests_submitted += requests as u64;
+        } else {
+            state.batches_failed += 1;
+        }
+        state.gas_burnt += gas_burnt.unwrap_or(0);
+    }
+
+    /// Records the time from a request's `timestamp` to its fulfilment.
+    pub fn observe_lag(&self, lag_ms: u64) {
+        self.state
+            .lock()
+            .unwrap()
+            .lag_seconds
+            .get_or_insert_with(|| Histogram::new(LAG_BUCKETS))
+            .observe(lag_ms as f64 / 1000.0);
+    }
+
+    /// Why the node is behind at `now`, if it is.
+    pub fn health(&self, now: u64) -> Result<(), String> {
+        let state = self.state.lock().unwrap();
+        let last_poll = state
+            .last_poll_ms
+            .ok_or_else(|| "no successful poll yet".to_string())?;
+        if now.saturating_sub(last_poll) > self.max_lag_ms {
+            return Err(format!("last successful poll {}ms ago", now - last_poll));
+        }
+        match state.oldest_unfulfilled_ms {
+            Some(oldest) if now.saturating_sub(oldest) > self.max_lag_ms => Err(format!(
+                "oldest unfulfilled request is {}ms old",
+                now - oldest
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut out = String::new();
+        let _ = writeln!(out, "# TYPE oracle_node_queue_depth gauge");
+        let _ = writeln!(out, "oracle_node_queue_depth {}", state.queue_depth);
+        let _ = writeln!(out, "# TYPE oracle_node_unfetchable_requests gauge");
+        let _ = writeln!(
+            out,
+            "oracle_node_unfetchable_requests {}",
+            state.unfetchable
+        );
+        let _ = writeln!(out, "# TYPE oracle_node_last_poll_timestamp_ms gauge");
+        let _ = writeln!(
+            out,
+            "oracle_node_last_poll_timestamp_ms {}",
+            state.last_poll_ms.unwrap_or(0)
+        );
+        let _ = writeln!(out, "# TYPE oracle_node_fetch_duration_seconds histogram");
+        for (host, histogram) in &state.fetch_seconds {
+            histogram.render(
+                &mut out,
+                "oracle_node_fetch_duration_seconds",
+                &format!("host=\"{}\",", host),
+            );
+        }
+        let _ = writeln!(out, "# TYPE oracle_node_batches_total counter");
+        let _ = writeln!(
+            out,
+            "oracle_node_batches_total{{outcome=\"success\"}} {}",
+            state.batches_succeeded
+        );
+        let _ = writeln!(
+            out,
+            "oracle_node_batches_total{{outcome=\"failure\"}} {}",
+            state.batches_failed
+        );
+        let _ = writeln!(out, "# TYPE oracle_node_submitted_requests_total counter");
+        let _ = writeln!(
+            out,
+            "oracle_node_submitted_requests_total {}",
+            state.requests_submitted
+        );
+        let _ = writeln!(out, "# TYPE oracle_node_batch_gas_burnt_total counter");
+        let _ = writeln!(out, "oracle_node_batch_gas_burnt_total {}", state.gas_burnt);
+        let _ = writeln!(out, "# TYPE oracle_node_fulfilment_lag_seconds histogram");
+        state
+            .lag_seconds
+            .clone()
+            .unwrap_or_else(|| Histogram::new(LAG_BUCKETS))
+            .render(&mut out, "oracle_node_fulfilment_lag_seconds", "");
+        out
+    }
+}
+
+/// Serves `/metrics` and `/healthz` (200, or 503 with the reason) on `listen` from a
+/// background thread, returning the bound address.
+pub fn serve(listen: &str, metrics: Arc<Metrics>) -> Result<SocketAddr, NodeError> {
+    let server = tiny_http::Server::http(listen)
+        .map_err(|err| NodeError::Config(format!("metrics listen {}: {}", listen, err)))?;
+    let addr = server
+        .server_addr()
+        .to_ip()
+        .ok_or_else(|| NodeError::Config(format!("metrics listen {}: not an ip", listen)))?;
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let (status, body) = match request.url() {
+                "/metrics" => (200, metrics.render()),
+                "/healthz" => match metrics.health(crate::node::now_ms()) {
+                    Ok(()) => (200, "ok\n".to_string()),
+                    Err(reason) => (503, format!("{}\n", reason)),
+                },
+                _ => (404, "not found\n".to_string()),
+            };
+            let _ =
+                request.respond(tiny_http::Response::from_string(body).with_status_code(status));
+        }
+    });
+    Ok(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_histograms_and_counters() {
+        let metrics = Metrics::new(60_000);
+        metrics.observe_fetch("api.example.com", Duration::from_millis(200));
+        metrics.observe_batch(true, 3, Some(5_000));
+        metrics.observe_batch(false, 2, None);
+        metrics.observe_lag(1_500);
+        let text = metrics.render();
+        for line in [
+            "oracle_node_fetch_duration_seconds_bucket{host=\"api.example.com\",le=\"0.1\"} 0",
+            "oracle_node_fetch_duration_seconds_bucket{host=\"api.example.com\",le=\"0.25\"} 1",
+            "oracle_node_fetch_duration_seconds_count{host=\"api.example.com\"} 1",
+            "oracle_node_batches_total{outcome=\"success\"} 1",
+            "oracle_node_batches_total{outcome=\"failure\"} 1",
+            "oracle_node_submitted_requests_total 3",
+            "oracle_node_batch_gas_burnt_total 5000",
+            "oracle_node_fulfilment_lag_seconds_bucket{le=\"2\"} 1",
+            "oracle_node_fulfilment_lag_seconds_sum 1.5",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {}", line);
+        }
+    }
+
+    #[test]
+    fn bounds_host_labels() {
+        let metrics = Metrics::new(60_000);
+        for index in 0..MAX_HOST_LABELS + 2 {
+            metrics.observe_fetch(&format!("{}.example.com", index), Duration::ZERO);
+        }
+        metrics.observe_fetch("0.example.com", Duration::ZERO);
+        let text = metrics.render();
+        assert!(text
+            .lines()
+            .any(|l| l == "oracle_node_fetch_duration_seconds_count{host=\"other\"} 2"));
+        assert!(text
+            .lines()
+            .any(|l| l == "oracle_node_fetch_duration_seconds_count{host=\"0.example.com\"} 2"));
+        assert_eq!(
+            metrics.state.lock().unwrap().fetch_seconds.len(),
+            MAX_HOST_LABELS + 1
+        );
+    }
+
+    #[test]
+    fn unhealthy_when_behind() {
+        let metrics = Metrics::new(1_000);
+        assert!(metrics.health(0).is_err());
+        metrics.observe_poll(10_000, 2, 0, Some(9_500));
+        assert!(metrics.health(10_400).is_ok());
+        // the oldest request is waiting too long
+        assert!(metrics.health(10_600).is_err());
+        metrics.observe_poll(10_600, 0, 0, None);
+        assert!(metrics.health(11_500).is_ok());
+        // no poll for too long
+        assert!(metrics.health(11_700).is_err());
+    }
+}
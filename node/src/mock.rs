@@ -0,0 +1,350 @@
+use crate::chain::{CallOutcome, ChainClient, TxStatus};
+use crate::node::now_ms;
+use crate::signer::SignerKey;
+use crate::stream::{BlockStream, ReceiptLogs, StreamBlock};
+use crate::transform::module_hash;
+use crate::NodeError;
+use near_oracle_types::client::{FunctionCall, ViewCall};
+use near_oracle_types::encoding::ResultEncoding;
+use near_oracle_types::{AwaitError, OracleData, OracleEvent, OracleEventKind};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::AccountId;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// In-process stand-in for the oracle contract, for tests and dry runs. Implements the views
+/// and calls the node uses with the contract's semantics; a successful call burns all the gas
+/// it attached. Sent transactions execute at once but stay pending until
+/// [`finalize`](Self::finalize). Executions log the contract's events.
+#[derive(Default)]
+pub struct MockChain {
+    queued: Mutex<Vec<OracleData>>,
+    views: Mutex<Vec<String>>,
+    calls: Mutex<Vec<FunctionCall>>,
+    logs: Mutex<Vec<String>>,
+    failing_calls: Mutex<usize>,
+    transforms: Mutex<HashMap<String, Vec<u8>>>,
+    nonces: Mutex<HashMap<String, u64>>,
+    /// Sent transactions by hash, with whether they are final.
+    txs: Mutex<Vec<(String, bool, TxStatus)>>,
+}
+
+#[derive(Deserialize)]
+struct PageArgs {
+    from_index: Option<u64>,
+    limit: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct IdArgs {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct HashArgs {
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct ExecuteArgs {
+    id: String,
+    return_value: String,
+}
+
+#[derive(Deserialize)]
+struct ExecuteBatchArgs {
+    ids: Vec<String>,
+    return_values: Vec<String>,
+}
+
+fn parse_args<'a, T: Deserialize<'a>>(args: &'a [u8]) -> Result<T, NodeError> {
+    serde_json::from_slice(args).map_err(|err| NodeError::Chain(err.to_string()))
+}
+
+impl MockChain {
+    pub fn new(queued: Vec<OracleData>) -> Self {
+        Self {
+            queued: Mutex::new(queued),
+            views: Mutex::default(),
+            calls: Mutex::default(),
+            logs: Mutex::default(),
+            failing_calls: Mutex::default(),
+            transforms: Mutex::default(),
+            nonces: Mutex::default(),
+            txs: Mutex::default(),
+        }
+    }
+    pub fn push(&self, oracle_data: OracleData) {
+        self.queued.lock().unwrap().push(oracle_data);
+    }
+    pub fn get(&self, id: &str) -> Option<OracleData> {
+        self.queued
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|oracle_data| oracle_data.id == id)
+            .cloned()
+    }
+    /// Makes the next `count` function calls fail without changing state.
+    pub fn fail_next_calls(&self, count: usize) {
+        *self.failing_calls.lock().unwrap() = count;
+    }
+    /// Stores a transform module as `register_transform` does, returning its hash.
+    pub fn register_transform(&self, module: Vec<u8>) -> String {
+        let hash = module_hash(&module);
+        self.transforms.lock().unwrap().insert(hash.clone(), module);
+        hash
+    }
+    /// Raises an access key's nonce as if another client had used the key.
+    pub fn use_nonce(&self, public_key: &str, nonce: u64) {
+        self.nonces
+            .lock()
+            .unwrap()
+            .insert(public_key.to_string(), nonce);
+    }
+    /// Makes every transaction sent so far final.
+    pub fn finalize(&self) {
+        for (_, is_final, _) in self.txs.lock().unwrap().iter_mut() {
+            *is_final = true;
+        }
+    }
+    /// Function calls received so far, in order.
+    pub fn calls(&self) -> Vec<FunctionCall> {
+        self.calls.lock().unwrap().clone()
+    }
+    /// Methods of the views run so far, in order.
+    pub fn views(&self) -> Vec<String> {
+        self.views.lock().unwrap().clone()
+    }
+    /// Event logs of the calls executed since the last take.
+    pub fn take_logs(&self) -> Vec<String> {
+        std::mem::take(&mut *self.logs.lock().unwrap())
+    }
+
+    fn log_event(&self, kind: OracleEventKind, ids: Vec<String>) {
+        self.logs
+            .lock()
+            .unwrap()
+            .push(OracleEvent::new(kind, ids).to_log());
+    }
+
+    fn execute(
+        queued: &mut [OracleData],
+        id: &str,
+        return_value: String,
+    ) -> Result<(), &'static str> {
+        let oracle_data = queued
+            .iter_mut()
+            .find(|oracle_data| oracle_data.id == id)
+            .ok_or("ERR_ORACLE_NOT_FOUND")?;
+        if oracle_data.executed {
+            return Err("ERR_ALREADY_EXECUTED");
+        }
+        let past_deadline = oracle_data
+            .deadline
+            .is_some_and(|deadline| now_ms() > deadline);
+        if oracle_data.error.is_some() || past_deadline {
+            return Err("ERR_ORACLE_EXPIRED");
+        }
+        if let Some(ResultEncoding::Borsh { value_type }) = &oracle_data.encoding {
+            if value_type.borsh_encode(&return_value).is_none() {
+                return Err("ERR_INVALID_VALUE_FOR_ENCODING");
+            }
+        }
+        oracle_data.executed = true;
+        oracle_data.return_value = Some(return_value);
+        Ok(())
+    }
+}
+
+impl ChainClient for MockChain {
+    fn view(&self, call: &ViewCall) -> Result<Vec<u8>, NodeError> {
+        self.views.lock().unwrap().push(call.method_name.clone());
+        let queued = self.queued.lock().unwrap();
+        let result = match call.method_name.as_str() {
+            "get_queued_data" => {
+                let args: PageArgs = parse_args(&call.args)?;
+                let page: Vec<_> = queued
+                    .iter()
+                    .skip(args.from_index.unwrap_or(0) as usize)
+                    .take(args.limit.unwrap_or(50) as usize)
+                    .collect();
+                serde_json::to_vec(&page)
+            }
+            "get_queued_data_by_id" => {
+                let args: IdArgs = parse_args(&call.args)?;
+                serde_json::to_vec(&queued.iter().find(|oracle_data| oracle_data.id == args.id))
+            }
+            "get_transform" => {
+                let args: HashArgs = parse_args(&call.args)?;
+                let module = self.transforms.lock().unwrap().get(&args.hash).cloned();
+                serde_json::to_vec(&module.map(Base64VecU8))
+            }
+            method => return Err(NodeError::Chain(format!("unknown view {}", method))),
+        };
+        Ok(result.unwrap())
+    }
+
+    fn call(&self, call: &FunctionCall) -> Result<CallOutcome, NodeError> {
+        self.calls.lock().unwrap().push(call.clone());
+        let mut failing_calls = self.failing_calls.lock().unwrap();
+        if *failing_calls > 0 {
+            *failing_calls -= 1;
+            return Err(NodeError::Chain("transaction failed".to_string()));
+        }
+        let mut queued = self.queued.lock().unwrap();
+        let outcome = CallOutcome {
+            tx_hash: None,
+            gas_burnt: Some(call.gas),
+        };
+        match call.method_name.as_str() {
+            "execute_oracle" => {
+                let args: ExecuteArgs = parse_args(&call.args)?;
+                Self::execute(&mut queued, &args.id, args.return_value)
+                    .map_err(|err| NodeError::Chain(err.to_string()))?;
+                self.log_event(OracleEventKind::OracleExecuted, vec![args.id]);
+                Ok(outcome)
+            }
+            "execute_oracle_batch" => {
+                let args: ExecuteBatchArgs = parse_args(&call.args)?;
+                if args.ids.len() != args.return_values.len() {
+                    return Err(NodeError::Chain("ERR_LENGTH_MISMATCH".to_string()));
+                }
+                // requests that cannot be executed are logged and skipped, not failed
+                let mut executed = vec![];
+                for (id, return_value) in args.ids.iter().zip(args.return_values) {
+                    match Self::execute(&mut queued, id, return_value) {
+                        Ok(()) => executed.push(id.clone()),
+                        Err(err) => self
+                            .logs
+                            .lock()
+                            .unwrap()
+                            .push(format!("Skipping {}: {}", id, err)),
+                    }
+                }
+                if !executed.is_empty() {
+                    self.log_event(OracleEventKind::OracleExecuted, executed);
+                }
+                Ok(outcome)
+            }
+            "expire_oracle" => {
+                let args: IdArgs = parse_args(&call.args)?;
+                let oracle_data = queued
+                    .iter_mut()
+                    .find(|oracle_data| oracle_data.id == args.id)
+                    .ok_or_else(|| NodeError::Chain("ERR_ORACLE_NOT_FOUND".to_string()))?;
+                if oracle_data.executed || oracle_data.error.is_some() {
+                    return Err(NodeError::Chain("ERR_ORACLE_ALREADY_RESOLVED".to_string()));
+                }
+                if oracle_data
+                    .deadline
+                    .is_none_or(|deadline| now_ms() <= deadline)
+                {
+                    return Err(NodeError::Chain("ERR_DEADLINE_NOT_REACHED".to_string()));
+                }
+                oracle_data.error = Some(AwaitError::TimedOut);
+                self.log_event(OracleEventKind::OracleExpired, vec![args.id]);
+                Ok(outcome)
+            }
+            method => Err(NodeError::Chain(format!("unknown method {}", method))),
+        }
+    }
+
+    fn access_key_nonce(&self, key: &SignerKey) -> Result<u64, NodeError> {
+        Ok(*self
+            .nonces
+            .lock()
+            .unwrap()
+            .get(&key.public_key())
+            .unwrap_or(&0))
+    }
+
+    fn send(&self, call: &FunctionCall, key: &SignerKey, nonce: u64) -> Result<String, NodeError> {
+        {
+            let mut nonces = self.nonces.lock().unwrap();
+            let ak_nonce = nonces.entry(key.public_key()).or_default();
+            if nonce <= *ak_nonce {
+                return Err(NodeError::InvalidNonce {
+                    ak_nonce: *ak_nonce,
+                });
+            }
+            *ak_nonce = nonce;
+        }
+        let mut txs = self.txs.lock().unwrap();
+        let tx_hash = format!("tx-{}", txs.len());
+        let status = match self.call(call) {
+            Ok(outcome) => TxStatus::Final(CallOutcome {
+                tx_hash: Some(tx_hash.clone()),
+                ..outcome
+            }),
+            Err(err) => TxStatus::Failed(err.to_string()),
+        };
+        txs.push((tx_hash.clone(), false, status));
+        Ok(tx_hash)
+    }
+
+    fn tx_status(&self, tx_hash: &str, _signer_id: &AccountId) -> Result<TxStatus, NodeError> {
+        Ok(self
+            .txs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(hash, _, _)| hash == tx_hash)
+            .map_or(TxStatus::Unknown, |(_, is_final, status)| match is_final {
+                true => status.clone(),
+                false => TxStatus::Pending,
+            }))
+    }
+}
+
+/// In-memory [`BlockStream`] for tests; clones share their blocks.
+#[derive(Default, Clone)]
+pub struct MockStream {
+    blocks: Arc<Mutex<MockBlocks>>,
+}
+
+#[derive(Default)]
+struct MockBlocks {
+    blocks: Vec<StreamBlock>,
+    height: u64,
+}
+
+impl MockStream {
+    /// Appends a block in which `executor_id` logged `logs`.
+    pub fn push(&self, executor_id: &AccountId, logs: Vec<String>) {
+        let mut blocks = self.blocks.lock().unwrap();
+        let block = StreamBlock {
+            height: blocks.height + 1,
+            prev_height: blocks.height,
+            receipts: vec![ReceiptLogs {
+                executor_id: executor_id.clone(),
+                logs,
+            }],
+        };
+        blocks.height = block.height;
+        blocks.blocks.push(block);
+    }
+    /// Loses the next block, as an indexer that fell behind would.
+    pub fn skip(&self) {
+        self.blocks.lock().unwrap().height += 1;
+    }
+}
+
+impl BlockStream for MockStream {
+    fn head(&self) -> Result<u64, NodeError> {
+        Ok(self.blocks.lock().unwrap().height)
+    }
+
+    fn blocks_after(&self, height: u64) -> Result<Vec<StreamBlock>, NodeError> {
+        Ok(self
+            .blocks
+            .lock()
+            .unwrap()
+            .blocks
+            .iter()
+            .filter(|block| block.height > height)
+            .cloned()
+            .collect())
+    }
+}
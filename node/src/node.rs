@@ -0,0 +1,594 @@
+use crate::chain::{ChainClient, TxStatus};
+use crate::config::Config;
+use crate::fetcher::HttpFetcher;
+use crate::metrics::Metrics;
+use crate::secrets::Secrets;
+use crate::store::{Job, JobStore, Stage};
+use crate::stream::{BlockStream, JsonlStream};
+use crate::submitter::{pack, Batch, Fulfilment, Submitter};
+use crate::transform::Transforms;
+use crate::NodeError;
+use near_oracle_types::client::{parse_view, OracleClient};
+use near_oracle_types::{OracleData, OracleEvent};
+use near_sdk::json_types::Base64VecU8;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Counts from one pass over the queue.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct PollReport {
+    /// Unexecuted requests found.
+    pub seen: usize,
+    pub fetched: usize,
+    pub fetch_failed: usize,
+    /// Fetches deferred by per-host rate limits or a host's `Retry-After`.
+    pub rate_limited: usize,
+    /// Values taken from the job store instead of being fetched again.
+    pub replayed: usize,
+    /// Requests skipped because an earlier submission may still land.
+    pub in_flight: usize,
+    pub submitted: usize,
+    pub batches: usize,
+    pub batch_failed: usize,
+    /// Submitted jobs observed executed on chain.
+    pub confirmed: usize,
+    /// Requests in signed transactions that became final.
+    pub finalized: usize,
+    /// Requests in signed transactions that failed; they are submitted again.
+    pub tx_failed: usize,
+    /// Requests a final transaction carried but the contract skipped; they are not
+    /// submitted again.
+    pub skipped: usize,
+    /// Requests past their deadline that the node resumed with a timeout.
+    pub expired: usize,
+    /// The block stream had a gap or failed, so the whole queue was read instead.
+    pub resynced: bool,
+}
+
+pub struct Node<C> {
+    config: Config,
+    chain: C,
+    client: OracleClient,
+    fetcher: HttpFetcher,
+    store: JobStore,
+    metrics: Arc<Metrics>,
+    transforms: Transforms,
+    submitter: Option<Submitter>,
+    stream: Option<Box<dyn BlockStream>>,
+    followed: Mutex<Option<Followed>>,
+}
+
+/// What became of a submission older than `store.resubmit_after_ms`.
+enum Recheck {
+    /// Its transaction is not final yet.
+    InFlight,
+    Executed,
+    /// Its transaction is final but the contract skipped the request.
+    Skipped,
+    /// The request was deleted.
+    Gone,
+    /// Nothing landed; the value has to be submitted again.
+    Resubmit,
+}
+
+/// The queue as followed from the block stream.
+struct Followed {
+    /// Last block applied.
+    height: u64,
+    /// Requests that may still need a value.
+    queue: Vec<OracleData>,
+}
+
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_millis() as u64)
+}
+
+impl<C: ChainClient> Node<C> {
+    pub fn new(config: Config, chain: C) -> Result<Self, NodeError> {
+        let metrics = Arc::new(Metrics::new(config.metrics.max_lag_ms));
+        Ok(Self {
+            client: OracleClient::new(config.chain.contract_id.clone()),
+            fetcher: HttpFetcher::new(
+                &config.fetch,
+                Secrets::load(&config.secrets)?,
+                metrics.clone(),
+            ),
+            store: JobStore::open(&config.store)?,
+            metrics,
+            transforms: Transforms::default(),
+            submitter: Submitter::load(&config.submitter, &config.chain.operator_id)?,
+            stream: config
+                .stream
+                .path
+                .clone()
+                .map(|path| Box::new(JsonlStream::new(path)) as Box<dyn BlockStream>),
+            followed: Mutex::default(),
+            config,
+            chain,
+        })
+    }
+
+    /// Follows the queue from `stream` instead of `stream.path`.
+    pub fn with_stream(mut self, stream: impl BlockStream + 'static) -> Self {
+        self.stream = Some(Box::new(stream));
+        *self.followed.get_mut().unwrap() = None;
+        self
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn chain(&self) -> &C {
+        &self.chain
+    }
+
+    pub fn store(&self) -> &JobStore {
+        &self.store
+    }
+
+    pub fn metrics(&self) -> &Arc<Metrics> {
+        &self.metrics
+    }
+
+    pub fn submitter(&self) -> Option<&Submitter> {
+        self.submitter.as_ref()
+    }
+
+    /// Every queued request. `get_queued_data_by_executed` filters after paging, so pages of
+    /// `get_queued_data` are read until a short one.
+    pub fn queue(&self) -> Result<Vec<OracleData>, NodeError> {
+        let page_size = self.config.poll.page_size.max(1);
+        let mut queue = vec![];
+        let mut from_index = 0;
+        loop {
+            let call = self
+                .client
+                .get_queued_data(Some(from_index), Some(page_size));
+            let page: Vec<OracleData> = parse_view(&self.chain.view(&call)?)
+                .map_err(|err| NodeError::Chain(err.to_string()))?;
+            let len = page.len() as u64;
+            queue.extend(page);
+            if len < page_size {
+                return Ok(queue);
+            }
+            from_index += len;
+        }
+    }
+
+    /// The queue for one poll, and whether it was read in full after the block stream
+    /// failed. With a stream, requests named by the contract's events since the last poll are
+    /// read by id; the whole queue is read at start and after a gap or stream error. Executed
+    /// requests are returned once after their event, then forgotten.
+    fn snapshot(&self, now: u64) -> Result<(Vec<OracleData>, bool), NodeError> {
+        let stream = match &self.stream {
+            Some(stream) => stream,
+            None => return Ok((self.queue()?, false)),
+        };
+        let mut followed = self.followed.lock().unwrap();
+        let mut resynced = false;
+        if let Some(state) = followed.as_mut() {
+            match self.follow(stream.as_ref(), state) {
+                Ok(()) => {
+                    let queue = state.queue.clone();
+                    state
+                        .queue
+                        .retain(|request| is_pending(request, now) || is_expirable(request, now));
+                    return Ok((queue, false));
+                }
+                Err(err) => {
+                    log::warn!("{}, reading the whole queue", err);
+                    resynced = true;
+                }
+            }
+        }
+        *followed = None;
+        // events after the head are applied on top of the queue read after it
+        let height = stream.head();
+        let queue = self.queue()?;
+        match height {
+            Ok(height) => {
+                *followed = Some(Followed {
+                    height,
+                    queue: queue
+                        .iter()
+                        .filter(|request| is_pending(request, now) || is_expirable(request, now))
+                        .cloned()
+                        .collect(),
+                })
+            }
+            Err(err) => log::warn!("{}", err),
+        }
+        Ok((queue, resynced))
+    }
+
+    /// Applies the stream's new blocks, reading every request an event of the contract
+    /// names. Fails on a block that does not follow the last one applied.
+    fn follow(&self, stream: &dyn BlockStream, state: &mut Followed) -> Result<(), NodeError> {
+        for block in stream.blocks_after(state.height)? {
+            if block.prev_height != state.height {
+                return Err(NodeError::Chain(format!(
+                    "stream: missed blocks {}..{}",
+                    state.height + 1,
+                    block.height
+                )));
+            }
+            let events = block
+                .receipts
+                .iter()
+                .filter(|receipt| receipt.executor_id == self.config.chain.contract_id)
+                .flat_map(|receipt| &receipt.logs)
+                .filter_map(|log| OracleEvent::from_log(log));
+            for event in events {
+                for id in event.ids() {
+                    let request = self.request(id)?;
+                    let known = state.queue.iter().position(|request| request.id == id);
+                    match (request, known) {
+                        (Some(request), Some(index)) => state.queue[index] = request,
+                        (Some(request), None) => state.queue.push(request),
+                        (None, Some(index)) => {
+                            state.queue.remove(index);
+                        }
+                        (None, None) => {}
+                    }
+                }
+            }
+            state.height = block.height;
+        }
+        Ok(())
+    }
+
+    /// Unexecuted requests that can still be fulfilled.
+    pub fn pending(&self) -> Result<Vec<OracleData>, NodeError> {
+        let now = now_ms();
+        Ok(self
+            .queue()?
+            .into_iter()
+            .filter(|oracle_data| is_pending(oracle_data, now))
+            .collect())
+    }
+
+    /// Fetches a request's value, running its transform, if any, over the response instead
+    /// of applying `path`. Transform modules are read from the contract on first use.
+    pub fn fetch(&self, request: &OracleData) -> Result<String, NodeError> {
+        let hash = match &request.transform {
+            Some(hash) => hash,
+            None => return self.fetcher.fetch(request),
+        };
+        if !self.transforms.is_loaded(hash) {
+            let module: Option<Base64VecU8> =
+                parse_view(&self.chain.view(&self.client.get_transform(hash))?)
+                    .map_err(|err| NodeError::Chain(err.to_string()))?;
+            let module =
+                module.ok_or_else(|| NodeError::Chain(format!("transform {} not found", hash)))?;
+            self.transforms.load(hash, &module.0)?;
+        }
+        self.fetcher
+            .fetch_with(request, |_, document| self.transforms.run(hash, document))
+    }
+
+    fn request(&self, id: &str) -> Result<Option<OracleData>, NodeError> {
+        parse_view(&self.chain.view(&self.client.get_queued_data_by_id(id))?)
+            .map_err(|err| NodeError::Chain(err.to_string()))
+    }
+
+    /// Checks the chain before a job submitted more than `store.resubmit_after_ms` ago is
+    /// submitted again: its transaction may still be pending, or the request may have been
+    /// executed since the queue was read.
+    fn recheck(&self, job: &Job) -> Result<Recheck, NodeError> {
+        if let Some(tx_hash) = &job.tx_hash {
+            match self
+                .chain
+                .tx_status(tx_hash, &self.config.chain.operator_id)?
+            {
+                TxStatus::Pending => return Ok(Recheck::InFlight),
+                // a final batch skips the requests it cannot execute, so each is read back
+                TxStatus::Final(_) => {
+                    return Ok(match self.request(&job.id)? {
+                        Some(request) if request.executed => Recheck::Executed,
+                        Some(_) => Recheck::Skipped,
+                        None => Recheck::Gone,
+                    })
+                }
+                TxStatus::Unknown | TxStatus::Failed(_) => {}
+            }
+        }
+        Ok(match self.request(&job.id)? {
+            Some(request) if request.executed => Recheck::Executed,
+            Some(_) => Recheck::Resubmit,
+            None => Recheck::Gone,
+        })
+    }
+
+    /// Records the outcome for a request carried by a final transaction: confirmed once it
+    /// reads back executed, skipped when the contract left it unexecuted.
+    fn settle_request(&self, id: &str, now: u64) -> Result<(), NodeError> {
+        match self.request(id)? {
+            Some(request) if request.executed => {
+                self.store.confirmed(id, now)?;
+            }
+            Some(_) => {
+                log::warn!("request {}: skipped by the contract", id);
+                self.store.skipped(id, now)?;
+            }
+            // deleted; its job is compacted away
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Fetches every pending request and submits the results in batches. Values already
+    /// fetched before a restart are submitted without fetching again, and requests are not
+    /// resubmitted within `store.resubmit_after_ms` of a submission, nor after it before the
+    /// chain shows that nothing landed. Requests left unfulfilled past their deadline are
+    /// expired, so their awaiters resume. Failed fetches, batches and expiries are left for the
+    /// next poll.
+    pub fn poll_once(&self) -> Result<PollReport, NodeError> {
+        let now = now_ms();
+        let (queue, resynced) = self.snapshot(now)?;
+        let mut report = PollReport {
+            resynced,
+            ..Default::default()
+        };
+        if let Some(submitter) = &self.submitter {
+            self.settle(submitter, now, &mut report)?;
+        }
+        let mut fulfilments = vec![];
+        let mut unfulfilled = HashMap::new();
+        for request in &queue {
+            if request.executed {
+                if self.store.confirmed(&request.id, now)? {
+                    report.confirmed += 1;
+                }
+                continue;
+            }
+            if is_expirable(request, now) {
+                let gas = self.config.batch.gas_per_request
+                    + request
+                        .callback
+                        .as_ref()
+                        .map_or(0, |callback| callback.gas.0);
+                match self
+                    .chain
+                    .call(&self.client.expire_oracle(&request.id, gas))
+                {
+                    Ok(_) => report.expired += 1,
+                    Err(err) => log::warn!("expire_oracle {}: {}", request.id, err),
+                }
+                continue;
+            }
+            if !is_pending(request, now) {
+                continue;
+            }
+            report.seen += 1;
+            let job = self.store.seen(&request.id, now)?;
+            // the contract refused the value; it is not submitted again
+            if job.stage == Stage::Skipped {
+                report.skipped += 1;
+                continue;
+            }
+            unfulfilled.insert(request.id.clone(), request.timestamp);
+            if job.stage == Stage::Submitted
+                && now.saturating_sub(job.updated_at) >= self.config.store.resubmit_after_ms
+            {
+                match self.recheck(&job) {
+                    Ok(Recheck::Resubmit) => {}
+                    Ok(Recheck::Executed) => {
+                        unfulfilled.remove(&request.id);
+                        if self.store.confirmed(&request.id, now)? {
+                            report.confirmed += 1;
+                        }
+                        continue;
+                    }
+                    Ok(Recheck::Skipped) => {
+                        log::warn!("request {}: skipped by the contract", request.id);
+                        unfulfilled.remove(&request.id);
+                        self.store.skipped(&request.id, now)?;
+                        report.skipped += 1;
+                        continue;
+                    }
+                    Ok(Recheck::Gone) => {
+                        unfulfilled.remove(&request.id);
+                        continue;
+                    }
+                    Ok(Recheck::InFlight) => {
+                        report.in_flight += 1;
+                        continue;
+                    }
+                    Err(err) => {
+                        log::warn!("request {}: {}", request.id, err);
+                        report.in_flight += 1;
+                        continue;
+                    }
+                }
+            }
+            let value = match (job.stage, job.value) {
+                (Stage::Submitted | Stage::Confirmed, _)
+                    if now.saturating_sub(job.updated_at) < self.config.store.resubmit_after_ms =>
+                {
+                    report.in_flight += 1;
+                    continue;
+                }
+                (Stage::Seen, _) | (_, None) => match self.fetch(request) {
+                    Ok(value) => {
+                        self.store.fetched(&request.id, &value, now)?;
+                        report.fetched += 1;
+                        value
+                    }
+                    // requests the node cannot fetch are counted apart, so a bad URL or a
+                    // host that keeps limiting the node does not hold back its lag
+                    Err(NodeError::RateLimited { .. }) => {
+                        unfulfilled.remove(&request.id);
+                        report.rate_limited += 1;
+                        continue;
+                    }
+                    Err(err) => {
+                        log::warn!("request {}: {}", request.id, err);
+                        unfulfilled.remove(&request.id);
+                        report.fetch_failed += 1;
+                        continue;
+                    }
+                },
+                (_, Some(value)) => {
+                    report.replayed += 1;
+                    value
+                }
+            };
+            fulfilments.push(Fulfilment {
+                id: request.id.clone(),
+                value,
+                gas: self.config.batch.gas_per_request
+                    + request
+                        .callback
+                        .as_ref()
+                        .map_or(0, |callback| callback.gas.0),
+            });
+        }
+
+        let batches = pack(fulfilments, &self.config.batch);
+        for batch in &batches {
+            self.store.submitted(&batch.ids(), now)?;
+        }
+        match &self.submitter {
+            Some(submitter) => {
+                for (batch, result) in submitter.submit(&self.chain, &self.client, batches, now) {
+                    match result {
+                        Ok(tx_hash) => {
+                            self.store.sent(&batch.ids(), &tx_hash)?;
+                            report.batches += 1;
+                            report.submitted += batch.fulfilments.len();
+                            self.observe_lag(&batch, &mut unfulfilled);
+                        }
+                        Err(err) => {
+                            log::error!("execute_oracle_batch: {}", err);
+                            report.batch_failed += 1;
+                            self.metrics
+                                .observe_batch(false, batch.fulfilments.len(), None);
+                        }
+                    }
+                }
+            }
+            None => {
+                for batch in batches {
+                    match self.chain.call(
+                        &self
+                            .client
+                            .execute_oracle_batch(&batch.results(), batch.gas),
+                    ) {
+                        Ok(outcome) => {
+                            if let Some(tx_hash) = &outcome.tx_hash {
+                                self.store.sent(&batch.ids(), tx_hash)?;
+                            }
+                            report.batches += 1;
+                            report.submitted += batch.fulfilments.len();
+                            self.metrics.observe_batch(
+                                true,
+                                batch.fulfilments.len(),
+                                outcome.gas_burnt,
+                            );
+                            self.observe_lag(&batch, &mut unfulfilled);
+                        }
+                        Err(err) => {
+                            log::error!("execute_oracle_batch: {}", err);
+                            report.batch_failed += 1;
+                            self.metrics
+                                .observe_batch(false, batch.fulfilments.len(), None);
+                        }
+                    }
+                }
+            }
+        }
+        self.metrics.observe_poll(
+            now,
+            report.seen,
+            report.fetch_failed + report.rate_limited + report.skipped,
+            unfulfilled.into_values().min(),
+        );
+
+        let live: HashSet<String> = queue.into_iter().map(|request| request.id).collect();
+        self.store
+            .compact(now, self.config.store.retention_ms, &live)?;
+        Ok(report)
+    }
+
+    /// Records what became of signed transactions: the requests of final batches are read
+    /// back to confirm or skip their jobs, failed batches return them to `Fetched`.
+    fn settle(
+        &self,
+        submitter: &Submitter,
+        now: u64,
+        report: &mut PollReport,
+    ) -> Result<(), NodeError> {
+        let give_up_after_ms = self.config.store.resubmit_after_ms;
+        for (sent, status) in submitter.track(&self.chain, now, give_up_after_ms) {
+            let ids = sent.batch.ids();
+            match status {
+                TxStatus::Final(outcome) => {
+                    for id in &ids {
+                        if let Err(err) = self.settle_request(id, now) {
+                            // left submitted; `recheck` reads it again later
+                            log::warn!("request {}: {}", id, err);
+                        }
+                    }
+                    report.finalized += ids.len();
+                    self.metrics
+                        .observe_batch(true, ids.len(), outcome.gas_burnt);
+                }
+                TxStatus::Failed(err) => {
+                    log::warn!("tx {}: {}", sent.tx_hash, err);
+                    self.store.failed(&ids, now)?;
+                    report.tx_failed += ids.len();
+                    self.metrics.observe_batch(false, ids.len(), None);
+                }
+                TxStatus::Unknown | TxStatus::Pending => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the lag of the batch's requests, which are fulfilled now.
+    fn observe_lag(&self, batch: &Batch, unfulfilled: &mut HashMap<String, u64>) {
+        let fulfilled_at = now_ms();
+        for id in batch.ids() {
+            if let Some(timestamp) = unfulfilled.remove(&id) {
+                self.metrics
+                    .observe_lag(fulfilled_at.saturating_sub(timestamp));
+            }
+        }
+    }
+
+    /// Polls every `poll.interval_ms` until `shutdown` is set.
+    pub fn run(&self, shutdown: &AtomicBool) {
+        let interval = Duration::from_millis(self.config.poll.interval_ms);
+        while !shutdown.load(Ordering::Relaxed) {
+            match self.poll_once() {
+                Ok(report) if report.seen > 0 || report.expired > 0 => log::info!("{:?}", report),
+                Ok(_) => {}
+                Err(err) => log::error!("poll: {}", err),
+            }
+            thread::sleep(interval);
+        }
+        if let Err(err) = self.store.flush() {
+            log::error!("{}", err);
+        }
+    }
+}
+
+pub(crate) fn is_pending(oracle_data: &OracleData, now: u64) -> bool {
+    !oracle_data.executed
+        && oracle_data.error.is_none()
+        && oracle_data.deadline.is_none_or(|deadline| now <= deadline)
+}
+
+/// Whether the request's deadline passed before it was fulfilled, so `expire_oracle` can
+/// resume its awaiter.
+pub(crate) fn is_expirable(oracle_data: &OracleData, now: u64) -> bool {
+    !oracle_data.executed
+        && oracle_data.error.is_none()
+        && oracle_data.deadline.is_some_and(|deadline| now > deadline)
+}
@@ -0,0 +1,90 @@
+use crate::config::HostLimit;
+use std::time::{Duration, Instant};
+
+/// Token bucket holding up to `burst` requests, refilled at `rate_per_sec`.
+#[derive(Clone, Debug)]
+pub struct TokenBucket {
+    capacity: f64,
+    rate_per_sec: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(limit: &HostLimit, now: Instant) -> Self {
+        let capacity = limit.burst.max(1) as f64;
+        Self {
+            capacity,
+            rate_per_sec: limit.rate_per_sec,
+            tokens: capacity,
+            updated: now,
+        }
+    }
+
+    /// Takes a token, or returns how long until one is available.
+    pub fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate_per_sec).min(self.capacity);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        if self.rate_per_sec <= 0.0 {
+            return Err(Duration::MAX);
+        }
+        Err(Duration::from_secs_f64(
+            (1.0 - self.tokens) / self.rate_per_sec,
+        ))
+    }
+}
+
+/// Parses a `Retry-After` value given in seconds, capped at `max`. HTTP dates are not
+/// supported and give `None`, as does a missing header.
+pub fn parse_retry_after(value: Option<&str>, max: Duration) -> Option<Duration> {
+    let secs: u64 = value?.trim().parse().ok()?;
+    Some(Duration::from_secs(secs).min(max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_allows_burst_then_refills() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(
+            &HostLimit {
+                rate_per_sec: 2.0,
+                burst: 2,
+            },
+            start,
+        );
+        assert!(bucket.try_take(start).is_ok());
+        assert!(bucket.try_take(start).is_ok());
+        assert_eq!(bucket.try_take(start), Err(Duration::from_millis(500)));
+        assert!(bucket.try_take(start + Duration::from_millis(500)).is_ok());
+    }
+
+    #[test]
+    fn retry_after_in_seconds() {
+        let max = Duration::from_secs(3_600);
+        assert_eq!(
+            parse_retry_after(Some(" 120"), max),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after(Some("Wed, 21 Oct 2015 07:28:00 GMT"), max),
+            None
+        );
+        assert_eq!(parse_retry_after(None, max), None);
+    }
+
+    #[test]
+    fn caps_huge_retry_after() {
+        let max = Duration::from_secs(3_600);
+        let retry_after = parse_retry_after(Some("18446744073709551615"), max).unwrap();
+        assert_eq!(retry_after, max);
+        assert!(Instant::now().checked_add(retry_after).is_some());
+    }
+}
@@ -0,0 +1,193 @@
+use crate::config::SecretsConfig;
+use crate::NodeError;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+const PLACEHOLDER_START: &str = "{{secret:";
+const PLACEHOLDER_END: &str = "}}";
+const REDACTED: &str = "[REDACTED]";
+
+/// An entry of the secrets file:
+///
+/// ```toml
+/// [coingecko_key]
+/// env = "COINGECKO_KEY"        # or: value = "..."
+/// hosts = ["pro-api.coingecko.com"]
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SecretEntry {
+    value: Option<String>,
+    env: Option<String>,
+    hosts: Vec<String>,
+}
+
+struct Secret {
+    value: String,
+    hosts: Vec<String>,
+}
+
+/// Values for `{{secret:<name>}}` placeholders in request URLs and headers. A secret is only
+/// filled in for the hosts it lists, so a request cannot send it anywhere else.
+#[derive(Default)]
+pub struct Secrets {
+    secrets: HashMap<String, Secret>,
+}
+
+impl Secrets {
+    pub fn load(config: &SecretsConfig) -> Result<Self, NodeError> {
+        match &config.path {
+            Some(path) => {
+                let source = std::fs::read_to_string(path)
+                    .map_err(|err| NodeError::Config(format!("{}: {}", path.display(), err)))?;
+                Self::from_toml(&source, |name| std::env::var(name).ok())
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Parses a secrets file, reading `env` entries through `env`.
+    pub fn from_toml(
+        source: &str,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, NodeError> {
+        let entries: HashMap<String, SecretEntry> =
+            toml::from_str(source).map_err(|err| NodeError::Config(err.to_string()))?;
+        let mut secrets = HashMap::new();
+        for (name, entry) in entries {
+            let value = match (entry.value, entry.env) {
+                (Some(value), None) => value,
+                (None, Some(var)) => env(&var).ok_or_else(|| {
+                    NodeError::Config(format!("secret {}: {} is not set", name, var))
+                })?,
+                _ => {
+                    return Err(NodeError::Config(format!(
+                        "secret {}: set exactly one of value and env",
+                        name
+                    )))
+                }
+            };
+            if value.is_empty() {
+                return Err(NodeError::Config(format!("secret {} is empty", name)));
+            }
+            secrets.insert(
+                name,
+                Secret {
+                    value,
+                    hosts: entry.hosts,
+                },
+            );
+        }
+        Ok(Self { secrets })
+    }
+
+    /// Replaces the placeholders in `template` for a request to `host`.
+    pub fn resolve(&self, template: &str, host: &str) -> Result<String, NodeError> {
+        let mut resolved = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find(PLACEHOLDER_START) {
+            resolved.push_str(&rest[..start]);
+            let after = &rest[start + PLACEHOLDER_START.len()..];
+            let end = after
+                .find(PLACEHOLDER_END)
+                .ok_or_else(|| NodeError::Fetch("unterminated secret placeholder".to_string()))?;
+            let name = &after[..end];
+            let secret = self
+                .secrets
+                .get(name)
+                .ok_or_else(|| NodeError::Fetch(format!("unknown secret {}", name)))?;
+            if !secret.hosts.iter().any(|allowed| allowed == host) {
+                return Err(NodeError::Fetch(format!(
+                    "secret {} is not allowed for {}",
+                    name, host
+                )));
+            }
+            resolved.push_str(&secret.value);
+            rest = &after[end + PLACEHOLDER_END.len()..];
+        }
+        resolved.push_str(rest);
+        Ok(resolved)
+    }
+
+    /// Whether `text` contains any secret value.
+    pub fn leaks(&self, text: &str) -> bool {
+        self.secrets
+            .values()
+            .any(|secret| text.contains(&secret.value))
+    }
+
+    /// `text` with every secret value replaced by `[REDACTED]`, for logs and errors.
+    pub fn redact(&self, text: &str) -> String {
+        self.secrets
+            .values()
+            .fold(text.to_string(), |text, secret| {
+                text.replace(&secret.value, REDACTED)
+            })
+    }
+
+    pub fn redact_error(&self, err: NodeError) -> NodeError {
+        match err {
+            NodeError::Fetch(message) => NodeError::Fetch(self.redact(&message)),
+            NodeError::Chain(message) => NodeError::Chain(self.redact(&message)),
+            err => err,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secrets() -> Secrets {
+        Secrets::from_toml(
+            r#"
+            [api_key]
+            value = "s3cret"
+            hosts = ["api.example.com"]
+
+            [token]
+            env = "TOKEN"
+            hosts = ["api.example.com", "data.example.org"]
+            "#,
+            |name| (name == "TOKEN").then(|| "t0ken".to_string()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn resolves_placeholders_for_allowed_hosts_only() {
+        let secrets = secrets();
+        assert_eq!(
+            secrets
+                .resolve(
+                    "https://api.example.com/p?key={{secret:api_key}}&t={{secret:token}}",
+                    "api.example.com"
+                )
+                .unwrap(),
+            "https://api.example.com/p?key=s3cret&t=t0ken"
+        );
+        assert!(secrets
+            .resolve("key={{secret:api_key}}", "data.example.org")
+            .is_err());
+        assert!(secrets
+            .resolve("{{secret:missing}}", "api.example.com")
+            .is_err());
+        assert_eq!(secrets.resolve("no secrets", "any").unwrap(), "no secrets");
+    }
+
+    #[test]
+    fn redacts_values() {
+        let secrets = secrets();
+        assert_eq!(
+            secrets.redact("GET https://api.example.com/p?key=s3cret failed"),
+            "GET https://api.example.com/p?key=[REDACTED] failed"
+        );
+        assert!(secrets.leaks("echo t0ken"));
+    }
+
+    #[test]
+    fn rejects_unset_env() {
+        let result = Secrets::from_toml("[token]\nenv = \"TOKEN\"\nhosts = []", |_| None);
+        assert!(matches!(result, Err(NodeError::Config(_))));
+    }
+}
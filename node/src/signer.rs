@@ -0,0 +1,177 @@
+use crate::NodeError;
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+use near_oracle_types::client::FunctionCall;
+use near_sdk::borsh::{self, BorshSerialize};
+use near_sdk::AccountId;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+const ED25519: u8 = 0;
+
+/// A full or function-call access key the node signs transactions with.
+pub struct SignerKey {
+    pub account_id: AccountId,
+    keypair: Keypair,
+}
+
+/// `near` CLI credentials file.
+#[derive(Deserialize)]
+struct Credentials {
+    account_id: AccountId,
+    #[serde(alias = "secret_key")]
+    private_key: String,
+}
+
+#[derive(BorshSerialize)]
+struct Ed25519PublicKey {
+    key_type: u8,
+    data: [u8; 32],
+}
+
+#[derive(BorshSerialize)]
+struct FunctionCallAction {
+    method_name: String,
+    args: Vec<u8>,
+    gas: u64,
+    deposit: u128,
+}
+
+/// Leading variants of NEAR's `Action`, so `FunctionCall` gets its index (2).
+#[derive(BorshSerialize)]
+#[allow(dead_code)]
+enum Action {
+    CreateAccount,
+    DeployContract { code: Vec<u8> },
+    FunctionCall(FunctionCallAction),
+}
+
+/// Borsh layout of a NEAR transaction.
+#[derive(BorshSerialize)]
+struct Transaction {
+    signer_id: String,
+    public_key: Ed25519PublicKey,
+    nonce: u64,
+    receiver_id: String,
+    block_hash: [u8; 32],
+    actions: Vec<Action>,
+}
+
+/// A signed transaction ready to broadcast.
+pub struct SignedTransaction {
+    /// Base58 transaction hash.
+    pub hash: String,
+    /// Borsh `SignedTransaction`.
+    pub bytes: Vec<u8>,
+}
+
+impl SignerKey {
+    /// Parses an `ed25519:<base58>` secret key of `account_id`.
+    pub fn from_secret(account_id: AccountId, secret: &str) -> Result<Self, NodeError> {
+        let bytes = secret
+            .strip_prefix("ed25519:")
+            .and_then(|key| bs58::decode(key).into_vec().ok())
+            .ok_or_else(|| NodeError::Config(format!("{}: invalid secret key", account_id)))?;
+        let keypair = match bytes.len() {
+            64 => Keypair::from_bytes(&bytes).ok(),
+            32 => SecretKey::from_bytes(&bytes).ok().map(|secret| Keypair {
+                public: PublicKey::from(&secret),
+                secret,
+            }),
+            _ => None,
+        }
+        .ok_or_else(|| NodeError::Config(format!("{}: invalid secret key", account_id)))?;
+        Ok(Self {
+            account_id,
+            keypair,
+        })
+    }
+
+    /// Reads a `near` CLI credentials file.
+    pub fn load(path: &Path) -> Result<Self, NodeError> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|err| NodeError::Config(format!("{}: {}", path.display(), err)))?;
+        let credentials: Credentials = serde_json::from_str(&source)
+            .map_err(|err| NodeError::Config(format!("{}: {}", path.display(), err)))?;
+        Self::from_secret(credentials.account_id, &credentials.private_key)
+    }
+
+    /// `ed25519:<base58>` public key, as RPC and the access key list show it.
+    pub fn public_key(&self) -> String {
+        format!(
+            "ed25519:{}",
+            bs58::encode(self.keypair.public.as_bytes()).into_string()
+        )
+    }
+
+    /// Signs `call` as a single function call action.
+    pub fn sign(&self, call: &FunctionCall, nonce: u64, block_hash: [u8; 32]) -> SignedTransaction {
+        let transaction = Transaction {
+            signer_id: self.account_id.to_string(),
+            public_key: Ed25519PublicKey {
+                key_type: ED25519,
+                data: self.keypair.public.to_bytes(),
+            },
+            nonce,
+            receiver_id: call.receiver_id.to_string(),
+            block_hash,
+            actions: vec![Action::FunctionCall(FunctionCallAction {
+                method_name: call.method_name.clone(),
+                args: call.args.clone(),
+                gas: call.gas,
+                deposit: call.deposit,
+            })],
+        };
+        let mut bytes = transaction.try_to_vec().unwrap();
+        let hash = Sha256::digest(&bytes);
+        let signature = self.keypair.sign(&hash);
+        bytes.push(ED25519);
+        bytes.extend_from_slice(&signature.to_bytes());
+        SignedTransaction {
+            hash: bs58::encode(hash).into_string(),
+            bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, Verifier};
+
+    #[test]
+    fn signs_function_call_transaction() {
+        let secret = format!("ed25519:{}", bs58::encode([7u8; 32]).into_string());
+        let key = SignerKey::from_secret("operator.testnet".parse().unwrap(), &secret).unwrap();
+        let call = FunctionCall {
+            receiver_id: "oracle.testnet".parse().unwrap(),
+            method_name: "execute_oracle_batch".to_string(),
+            args: b"{}".to_vec(),
+            gas: 30_000_000_000_000,
+            deposit: 0,
+        };
+        let signed = key.sign(&call, 5, [1; 32]);
+
+        let (transaction, signature) = signed.bytes.split_at(signed.bytes.len() - 65);
+        assert_eq!(
+            bs58::encode(Sha256::digest(transaction)).into_string(),
+            signed.hash
+        );
+        // signer id, key type and key, then the nonce
+        let key_at = 4 + "operator.testnet".len();
+        assert_eq!(transaction[key_at], ED25519);
+        assert_eq!(&transaction[key_at + 33..key_at + 41], &5u64.to_le_bytes());
+        // one action, a function call
+        let receiver_at = key_at + 41;
+        let actions_at = receiver_at + 4 + "oracle.testnet".len() + 32;
+        assert_eq!(&transaction[actions_at..actions_at + 5], &[1, 0, 0, 0, 2]);
+        assert_eq!(signature[0], ED25519);
+        let signature = Signature::from_bytes(&signature[1..]).unwrap();
+        assert!(key
+            .keypair
+            .public
+            .verify(&Sha256::digest(transaction), &signature)
+            .is_ok());
+        assert!(key.public_key().starts_with("ed25519:"));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::config::StoreConfig;
+use crate::NodeError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// How far the node got with a request. Stages only move forward, except that a
+/// transaction known to have failed returns its jobs to `Fetched`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Seen,
+    /// The value is stored and is submitted as is after a restart.
+    Fetched,
+    /// A transaction carrying the value was sent; its outcome is not known yet.
+    Submitted,
+    /// A final transaction carried the value but the contract skipped the request (e.g. the
+    /// value does not fit the request's encoding); it is not submitted again.
+    Skipped,
+    /// The request was observed executed on chain.
+    Confirmed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Job {
+    pub id: String,
+    pub stage: Stage,
+    pub value: Option<String>,
+    /// Time (ms) of the last stage change.
+    pub updated_at: u64,
+    pub submissions: u32,
+    /// Hash of the transaction carrying the latest submission, once it was accepted.
+    #[serde(default)]
+    pub tx_hash: Option<String>,
+}
+
+fn store_error(err: impl ToString) -> NodeError {
+    NodeError::Store(err.to_string())
+}
+
+/// Durable per-request job state, so a restarted node neither refetches values it already
+/// has nor resubmits transactions that may have landed.
+pub struct JobStore {
+    db: sled::Db,
+}
+
+impl JobStore {
+    /// Opens the store at `config.path`, or an in-memory store without one.
+    pub fn open(config: &StoreConfig) -> Result<Self, NodeError> {
+        let db = match &config.path {
+            Some(path) => sled::open(path),
+            None => sled::Config::new().temporary(true).open(),
+        }
+        .map_err(store_error)?;
+        Ok(Self { db })
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<Job>, NodeError> {
+        self.db
+            .get(id)
+            .map_err(store_error)?
+            .map(|bytes| serde_json::from_slice(&bytes).map_err(store_error))
+            .transpose()
+    }
+
+    fn put(&self, job: &Job) -> Result<(), NodeError> {
+        let bytes = serde_json::to_vec(job).map_err(store_error)?;
+        self.db.insert(&job.id, bytes).map_err(store_error)?;
+        Ok(())
+    }
+
+    pub fn jobs(&self) -> Result<Vec<Job>, NodeError> {
+        self.db
+            .iter()
+            .values()
+            .map(|bytes| serde_json::from_slice(&bytes.map_err(store_error)?).map_err(store_error))
+            .collect()
+    }
+
+    /// Returns the job for `id`, recording it as `Seen` the first time.
+    pub fn seen(&self, id: &str, now: u64) -> Result<Job, NodeError> {
+        if let Some(job) = self.get(id)? {
+            return Ok(job);
+        }
+        let job = Job {
+            id: id.to_string(),
+            stage: Stage::Seen,
+            value: None,
+            updated_at: now,
+            submissions: 0,
+            tx_hash: None,
+        };
+        self.put(&job)?;
+        Ok(job)
+    }
+
+    pub fn fetched(&self, id: &str, value: &str, now: u64) -> Result<(), NodeError> {
+        let mut job = self.seen(id, now)?;
+        job.stage = Stage::Fetched;
+        job.value = Some(value.to_string());
+        job.updated_at = now;
+        self.put(&job)
+    }
+
+    /// Marks jobs submitted and flushes to disk; call it before sending the transaction.
+    pub fn submitted(&self, ids: &[String], now: u64) -> Result<(), NodeError> {
+        for id in ids {
+            let mut job = self.seen(id, now)?;
+            job.stage = Stage::Submitted;
+            job.submissions += 1;
+            job.updated_at = now;
+            job.tx_hash = None;
+            self.put(&job)?;
+        }
+        self.db.flush().map_err(store_error)?;
+        Ok(())
+    }
+
+    /// Records the hash of the transaction that carries submitted jobs.
+    pub fn sent(&self, ids: &[String], tx_hash: &str) -> Result<(), NodeError> {
+        for id in ids {
+            if let Some(mut job) = self.get(id)? {
+                if job.stage == Stage::Submitted {
+                    job.tx_hash = Some(tx_hash.to_string());
+                    self.put(&job)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns submitted jobs to `Fetched` after their transaction failed, so their values
+    /// are submitted again without waiting for `resubmit_after_ms`.
+    pub fn failed(&self, ids: &[String], now: u64) -> Result<(), NodeError> {
+        for id in ids {
+            if let Some(mut job) = self.get(id)? {
+                if job.stage == Stage::Submitted {
+                    job.stage = Stage::Fetched;
+                    job.updated_at = now;
+                    self.put(&job)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks a submitted job skipped, returning whether it changed.
+    pub fn skipped(&self, id: &str, now: u64) -> Result<bool, NodeError> {
+        match self.get(id)? {
+            Some(mut job) if job.stage == Stage::Submitted => {
+                job.stage = Stage::Skipped;
+                job.updated_at = now;
+                self.put(&job)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Marks a known job confirmed, returning whether it changed.
+    pub fn confirmed(&self, id: &str, now: u64) -> Result<bool, NodeError> {
+        match self.get(id)? {
+            Some(mut job) if job.stage != Stage::Confirmed => {
+                job.stage = Stage::Confirmed;
+                job.updated_at = now;
+                self.put(&job)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Drops confirmed jobs and jobs of requests no longer queued (`live` holds the queued
+    /// ids) once they are older than `retention_ms`. Returns the number removed.
+    pub fn compact(
+        &self,
+        now: u64,
+        retention_ms: u64,
+        live: &HashSet<String>,
+    ) -> Result<usize, NodeError> {
+        let mut removed = 0;
+        for job in self.jobs()? {
+            let expired = now.saturating_sub(job.updated_at) >= retention_ms;
+            if expired && (job.stage == Stage::Confirmed || !live.contains(&job.id)) {
+                self.db.remove(&job.id).map_err(store_error)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    pub fn flush(&self) -> Result<(), NodeError> {
+        self.db.flush().map_err(store_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages_and_compaction() {
+        let store = JobStore::open(&StoreConfig::default()).unwrap();
+        assert_eq!(store.seen("a", 1).unwrap().stage, Stage::Seen);
+        store.fetched("a", "42", 2).unwrap();
+        // seeing a known job again keeps its stage
+        assert_eq!(store.seen("a", 3).unwrap().stage, Stage::Fetched);
+        store.submitted(&["a".to_string()], 4).unwrap();
+        store.sent(&["a".to_string()], "h").unwrap();
+        assert_eq!(
+            store.get("a").unwrap().unwrap().tx_hash.as_deref(),
+            Some("h")
+        );
+        assert!(store.skipped("a", 5).unwrap());
+        assert!(!store.skipped("a", 5).unwrap());
+        assert!(store.confirmed("a", 5).unwrap());
+        assert!(!store.confirmed("a", 6).unwrap());
+        store.seen("b", 5).unwrap();
+
+        let live: HashSet<_> = ["a".to_string(), "b".to_string()].into();
+        assert_eq!(store.compact(10, 10, &live).unwrap(), 0);
+        assert_eq!(store.compact(15, 10, &live).unwrap(), 1);
+        assert_eq!(store.compact(15, 10, &HashSet::new()).unwrap(), 1);
+        assert!(store.jobs().unwrap().is_empty());
+    }
+}
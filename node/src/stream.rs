@@ -0,0 +1,130 @@
+use crate::NodeError;
+use near_sdk::AccountId;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Logs of one executed receipt.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ReceiptLogs {
+    pub executor_id: AccountId,
+    pub logs: Vec<String>,
+}
+
+/// A final block and the logs of the receipts executed in it.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct StreamBlock {
+    pub height: u64,
+    /// Height of the block before it. Heights may skip, so this, not `height - 1`, tells
+    /// whether a block was missed.
+    pub prev_height: u64,
+    #[serde(default)]
+    pub receipts: Vec<ReceiptLogs>,
+}
+
+/// A source of final blocks, e.g. an indexer following the chain.
+pub trait BlockStream: Send + Sync {
+    /// Height of the newest block available.
+    fn head(&self) -> Result<u64, NodeError>;
+    /// Available blocks after `height`, oldest first.
+    fn blocks_after(&self, height: u64) -> Result<Vec<StreamBlock>, NodeError>;
+}
+
+/// Blocks appended as JSON lines to a file by an indexer. Only complete lines are read, and
+/// only the bytes added since the last read.
+pub struct JsonlStream {
+    path: PathBuf,
+    state: Mutex<JsonlState>,
+}
+
+#[derive(Default)]
+struct JsonlState {
+    offset: u64,
+    blocks: Vec<StreamBlock>,
+}
+
+fn stream_error(err: impl std::fmt::Display) -> NodeError {
+    NodeError::Chain(format!("stream: {}", err))
+}
+
+impl JsonlStream {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Reads blocks appended since the last read.
+    fn read(&self, state: &mut JsonlState) -> Result<(), NodeError> {
+        let mut file = File::open(&self.path)
+            .map_err(|err| stream_error(format!("{}: {}", self.path.display(), err)))?;
+        let len = file.metadata().map_err(stream_error)?.len();
+        if len < state.offset {
+            // truncated or rotated
+            *state = JsonlState::default();
+        }
+        file.seek(SeekFrom::Start(state.offset))
+            .map_err(stream_error)?;
+        let mut appended = String::new();
+        file.read_to_string(&mut appended).map_err(stream_error)?;
+        let complete = appended.rfind('\n').map_or(0, |end| end + 1);
+        for line in appended[..complete]
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+        {
+            state
+                .blocks
+                .push(serde_json::from_str(line).map_err(stream_error)?);
+        }
+        state.offset += complete as u64;
+        Ok(())
+    }
+}
+
+impl BlockStream for JsonlStream {
+    fn head(&self) -> Result<u64, NodeError> {
+        let mut state = self.state.lock().unwrap();
+        self.read(&mut state)?;
+        Ok(state.blocks.last().map_or(0, |block| block.height))
+    }
+
+    fn blocks_after(&self, height: u64) -> Result<Vec<StreamBlock>, NodeError> {
+        let mut state = self.state.lock().unwrap();
+        self.read(&mut state)?;
+        state.blocks.retain(|block| block.height > height);
+        Ok(state.blocks.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn reads_complete_appended_lines() {
+        let path =
+            std::env::temp_dir().join(format!("oracle-node-{}-blocks.jsonl", std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, r#"{{"height": 10, "prev_height": 9}}"#).unwrap();
+        write!(
+            file,
+            r#"{{"height": 12, "prev_height": 10, "receipts": [{{"executor_id": "oracle.testnet", "logs": ["a"]}}]}}"#
+        )
+        .unwrap();
+        let stream = JsonlStream::new(path.clone());
+
+        // the second line is not complete yet
+        assert_eq!(stream.head().unwrap(), 10);
+        writeln!(file).unwrap();
+        let blocks = stream.blocks_after(10).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!((blocks[0].height, blocks[0].prev_height), (12, 10));
+        assert_eq!(blocks[0].receipts[0].logs, vec!["a".to_string()]);
+        assert_eq!(stream.head().unwrap(), 12);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+use crate::chain::{ChainClient, TxStatus};
+use crate::config::{BatchConfig, SubmitterConfig};
+use crate::signer::SignerKey;
+use crate::NodeError;
+use near_oracle_types::client::{FunctionCall, OracleClient, MAX_CALL_GAS};
+use near_sdk::AccountId;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::thread;
+
+/// A value ready to be submitted and the gas executing it needs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fulfilment {
+    pub id: String,
+    pub value: String,
+    pub gas: u64,
+}
+
+/// Fulfilments sent in one `execute_oracle_batch` call with `gas` attached.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Batch {
+    pub fulfilments: Vec<Fulfilment>,
+    pub gas: u64,
+}
+
+impl Batch {
+    pub fn ids(&self) -> Vec<String> {
+        self.fulfilments.iter().map(|f| f.id.clone()).collect()
+    }
+
+    pub fn results(&self) -> Vec<(String, String)> {
+        self.fulfilments
+            .iter()
+            .map(|f| (f.id.clone(), f.value.clone()))
+            .collect()
+    }
+}
+
+/// Packs fulfilments, in order, into batches of at most `max_requests` whose gas fits
+/// `gas_budget` (capped at [`MAX_CALL_GAS`]). A fulfilment needing more than the budget is
+/// sent alone.
+pub fn pack(fulfilments: Vec<Fulfilment>, config: &BatchConfig) -> Vec<Batch> {
+    let budget = config.gas_budget.min(MAX_CALL_GAS);
+    let max_requests = config.max_requests.max(1);
+    let mut batches: Vec<Batch> = vec![];
+    for fulfilment in fulfilments {
+        match batches.last_mut() {
+            Some(batch)
+                if batch.fulfilments.len() < max_requests
+                    && batch.gas + fulfilment.gas <= budget =>
+            {
+                batch.gas += fulfilment.gas;
+                batch.fulfilments.push(fulfilment);
+            }
+            _ => batches.push(Batch {
+                gas: fulfilment.gas.min(MAX_CALL_GAS),
+                fulfilments: vec![fulfilment],
+            }),
+        }
+    }
+    batches
+}
+
+struct KeySlot {
+    key: SignerKey,
+    /// Last nonce used, `None` until read from the chain.
+    nonce: Option<u64>,
+}
+
+/// A batch whose transaction was accepted but is not final yet.
+#[derive(Clone, Debug)]
+pub struct SentBatch {
+    pub tx_hash: String,
+    pub batch: Batch,
+    pub signer_id: AccountId,
+    pub sent_at: u64,
+}
+
+/// Sends batches in parallel over several access keys of the operator, one transaction per
+/// key at a time so nonces never race, and follows them until they are final.
+pub struct Submitter {
+    slots: Vec<Mutex<KeySlot>>,
+    nonce_retries: u32,
+    sent: Mutex<Vec<SentBatch>>,
+}
+
+impl Submitter {
+    pub fn new(keys: Vec<SignerKey>, config: &SubmitterConfig) -> Self {
+        Self {
+            slots: keys
+                .into_iter()
+                .map(|key| Mutex::new(KeySlot { key, nonce: None }))
+                .collect(),
+            nonce_retries: config.nonce_retries,
+            sent: Mutex::default(),
+        }
+    }
+
+    /// Loads the keys in `config.key_files`, which must belong to `operator_id`. Gives
+    /// `None` without key files.
+    pub fn load(
+        config: &SubmitterConfig,
+        operator_id: &AccountId,
+    ) -> Result<Option<Self>, NodeError> {
+        if config.key_files.is_empty() {
+            return Ok(None);
+        }
+        let keys = config
+            .key_files
+            .iter()
+            .map(|path| {
+                let key = SignerKey::load(path)?;
+                if &key.account_id != operator_id {
+                    return Err(NodeError::Config(format!(
+                        "{}: key of {}, not {}",
+                        path.display(),
+                        key.account_id,
+                        operator_id
+                    )));
+                }
+                Ok(key)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Some(Self::new(keys, config)))
+    }
+
+    /// Batches sent and not final yet.
+    pub fn in_flight(&self) -> Vec<SentBatch> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// Sends every batch, spreading them over the keys, and returns each batch with its
+    /// transaction hash or error. Accepted batches are tracked by [`track`](Self::track).
+    pub fn submit<C: ChainClient>(
+        &self,
+        chain: &C,
+        client: &OracleClient,
+        batches: Vec<Batch>,
+        now: u64,
+    ) -> Vec<(Batch, Result<String, NodeError>)> {
+        let queue = Mutex::new(batches.into_iter().collect::<VecDeque<_>>());
+        let results = Mutex::new(vec![]);
+        thread::scope(|scope| {
+            for slot in &self.slots {
+                scope.spawn(|| {
+                    let mut slot = slot.lock().unwrap();
+                    loop {
+                        let batch = match queue.lock().unwrap().pop_front() {
+                            Some(batch) => batch,
+                            None => break,
+                        };
+                        let call = client.execute_oracle_batch(&batch.results(), batch.gas);
+                        let result = self.send(chain, &mut slot, &call);
+                        if let Ok(tx_hash) = &result {
+                            self.sent.lock().unwrap().push(SentBatch {
+                                tx_hash: tx_hash.clone(),
+                                batch: batch.clone(),
+                                signer_id: slot.key.account_id.clone(),
+                                sent_at: now,
+                            });
+                        }
+                        results.lock().unwrap().push((batch, result));
+                    }
+                });
+            }
+        });
+        results.into_inner().unwrap()
+    }
+
+    /// Sends with the slot's next nonce, refreshing it and retrying after an invalid nonce.
+    fn send<C: ChainClient>(
+        &self,
+        chain: &C,
+        slot: &mut KeySlot,
+        call: &FunctionCall,
+    ) -> Result<String, NodeError> {
+        let mut attempts = 0;
+        loop {
+            let nonce = match slot.nonce {
+                Some(nonce) => nonce,
+                None => chain.access_key_nonce(&slot.key)?,
+            } + 1;
+            match chain.send(call, &slot.key, nonce) {
+                Ok(tx_hash) => {
+                    slot.nonce = Some(nonce);
+                    return Ok(tx_hash);
+                }
+                Err(NodeError::InvalidNonce { ak_nonce }) if attempts < self.nonce_retries => {
+                    attempts += 1;
+                    slot.nonce = Some(ak_nonce);
+                }
+                Err(err) => {
+                    // the key may have been used; read its nonce again next time
+                    slot.nonce = None;
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /// Checks sent batches, returning those that became final (`TxStatus::Final` or
+    /// `TxStatus::Failed`). Batches still pending after `give_up_after_ms` are dropped, as
+    /// their requests are resubmitted by then.
+    pub fn track<C: ChainClient>(
+        &self,
+        chain: &C,
+        now: u64,
+        give_up_after_ms: u64,
+    ) -> Vec<(SentBatch, TxStatus)> {
+        let sent = std::mem::take(&mut *self.sent.lock().unwrap());
+        let mut settled = vec![];
+        let mut pending = vec![];
+        for batch in sent {
+            match chain.tx_status(&batch.tx_hash, &batch.signer_id) {
+                Ok(TxStatus::Unknown | TxStatus::Pending) | Err(_)
+                    if now.saturating_sub(batch.sent_at) < give_up_after_ms =>
+                {
+                    pending.push(batch)
+                }
+                Ok(TxStatus::Unknown | TxStatus::Pending) | Err(_) => {
+                    log::warn!("tx {}: not final, giving up", batch.tx_hash)
+                }
+                Ok(status) => settled.push((batch, status)),
+            }
+        }
+        self.sent.lock().unwrap().extend(pending);
+        settled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fulfilment(id: &str, gas: u64) -> Fulfilment {
+        Fulfilment {
+            id: id.to_string(),
+            value: "1".to_string(),
+            gas,
+        }
+    }
+
+    #[test]
+    fn retries_with_refreshed_nonce() {
+        let chain = crate::MockChain::default();
+        let secret = format!("ed25519:{}", bs58::encode([3u8; 32]).into_string());
+        let key = SignerKey::from_secret("operator.testnet".parse().unwrap(), &secret).unwrap();
+        let public_key = key.public_key();
+        let submitter = Submitter::new(vec![key], &SubmitterConfig::default());
+        let client = OracleClient::new("oracle.testnet".parse().unwrap());
+        let batch = |id: &str| Batch {
+            fulfilments: vec![fulfilment(id, 10)],
+            gas: 10,
+        };
+
+        chain.use_nonce(&public_key, 5);
+        let sent = submitter.submit(&chain, &client, vec![batch("a")], 0);
+        assert!(sent[0].1.is_ok());
+        // another client used the key
+        chain.use_nonce(&public_key, 10);
+        let sent = submitter.submit(&chain, &client, vec![batch("b")], 0);
+        assert!(sent[0].1.is_ok());
+        let slot = submitter.slots[0].lock().unwrap();
+        assert_eq!(slot.nonce, Some(11));
+        assert_eq!(chain.access_key_nonce(&slot.key).unwrap(), 11);
+        assert_eq!(submitter.in_flight().len(), 2);
+    }
+
+    #[test]
+    fn packs_to_gas_budget_and_size() {
+        let config = BatchConfig {
+            max_requests: 3,
+            gas_per_request: 10,
+            gas_budget: 100,
+        };
+        let batches = pack(
+            vec![
+                fulfilment("a", 40),
+                fulfilment("b", 50),
+                fulfilment("c", 20),
+                fulfilment("d", 250),
+                fulfilment("e", 10),
+                fulfilment("f", 10),
+                fulfilment("g", 10),
+                fulfilment("h", 10),
+            ],
+            &config,
+        );
+        let packed: Vec<(Vec<String>, u64)> = batches
+            .iter()
+            .map(|batch| (batch.ids(), batch.gas))
+            .collect();
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            packed,
+            vec![
+                (ids(&["a", "b"]), 90),
+                (ids(&["c"]), 20),
+                (ids(&["d"]), 250),
+                (ids(&["e", "f", "g"]), 30),
+                (ids(&["h"]), 10),
+            ]
+        );
+    }
+}
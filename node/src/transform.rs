@@ -0,0 +1,202 @@
+use crate::NodeError;
+use near_oracle_types::transform::{
+    MAX_TRANSFORM_MEMORY, MAX_TRANSFORM_OUTPUT, MAX_TRANSFORM_SIZE, MAX_TRANSFORM_TABLE_ELEMENTS,
+    TRANSFORM_FUEL,
+};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use wasmi::{
+    Config, Engine, ExternType, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder,
+    TypedFunc,
+};
+
+const WASM_PAGE_SIZE: u64 = 64 * 1024;
+
+/// Runs the WASM transforms requests reference, in wasmi with [`TRANSFORM_FUEL`],
+/// [`MAX_TRANSFORM_MEMORY`] and no host functions, so every operator computes the same output
+/// from the same response. Modules are compiled once and kept by hash.
+pub struct Transforms {
+    engine: Engine,
+    modules: Mutex<HashMap<String, Module>>,
+}
+
+/// Hex SHA-256 of a module, as `register_transform` names it.
+pub fn module_hash(wasm: &[u8]) -> String {
+    Sha256::digest(wasm)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn transform_error(hash: &str, err: impl std::fmt::Display) -> NodeError {
+    NodeError::Fetch(format!("transform {}: {}", hash, err))
+}
+
+impl Default for Transforms {
+    fn default() -> Self {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        Self {
+            engine: Engine::new(&config),
+            modules: Mutex::default(),
+        }
+    }
+}
+
+impl Transforms {
+    pub fn is_loaded(&self, hash: &str) -> bool {
+        self.modules.lock().unwrap().contains_key(hash)
+    }
+
+    /// Compiles `wasm` after checking it hashes to `hash`.
+    pub fn load(&self, hash: &str, wasm: &[u8]) -> Result<(), NodeError> {
+        if wasm.len() > MAX_TRANSFORM_SIZE {
+            return Err(transform_error(hash, "module too large"));
+        }
+        let actual = module_hash(wasm);
+        if actual != hash {
+            return Err(transform_error(
+                hash,
+                format!("module hashes to {}", actual),
+            ));
+        }
+        let module = Module::new(&self.engine, wasm).map_err(|err| transform_error(hash, err))?;
+        match module.get_export("memory") {
+            Some(ExternType::Memory(memory))
+                if memory.minimum().saturating_mul(WASM_PAGE_SIZE)
+                    > MAX_TRANSFORM_MEMORY as u64 =>
+            {
+                return Err(transform_error(hash, "declared memory too large"));
+            }
+            Some(ExternType::Memory(_)) => {}
+            _ => return Err(transform_error(hash, "no memory export")),
+        }
+        self.modules
+            .lock()
+            .unwrap()
+            .insert(hash.to_string(), module);
+        Ok(())
+    }
+
+    /// Runs the loaded module `hash` over `input` in a fresh instance.
+    pub fn run(&self, hash: &str, input: &str) -> Result<String, NodeError> {
+        let module = self
+            .modules
+            .lock()
+            .unwrap()
+            .get(hash)
+            .cloned()
+            .ok_or_else(|| transform_error(hash, "not loaded"))?;
+        let err = |err: wasmi::Error| transform_error(hash, err);
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MAX_TRANSFORM_MEMORY)
+            .table_elements(MAX_TRANSFORM_TABLE_ELEMENTS)
+            .instances(1)
+            .memories(1)
+            .tables(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store.set_fuel(TRANSFORM_FUEL).map_err(err)?;
+        // no host functions: a module with imports fails to instantiate
+        let instance = Linker::<StoreLimits>::new(&self.engine)
+            .instantiate_and_start(&mut store, &module)
+            .map_err(err)?;
+        let memory: Memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| transform_error(hash, "no memory export"))?;
+        let alloc: TypedFunc<i32, i32> = instance.get_typed_func(&store, "alloc").map_err(err)?;
+        let transform: TypedFunc<(i32, i32), i64> =
+            instance.get_typed_func(&store, "transform").map_err(err)?;
+
+        let len =
+            i32::try_from(input.len()).map_err(|_| transform_error(hash, "input too large"))?;
+        let ptr = alloc.call(&mut store, len).map_err(err)?;
+        memory
+            .write(&mut store, ptr as u32 as usize, input.as_bytes())
+            .map_err(|err| transform_error(hash, err))?;
+        let packed = transform.call(&mut store, (ptr, len)).map_err(err)? as u64;
+        let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        if out_len > MAX_TRANSFORM_OUTPUT {
+            return Err(transform_error(hash, "output too large"));
+        }
+        let mut output = vec![0; out_len];
+        memory
+            .read(&store, out_ptr, &mut output)
+            .map_err(|err| transform_error(hash, err))?;
+        String::from_utf8(output).map_err(|err| transform_error(hash, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the input's first `n` bytes, looping `spin` times first.
+    fn module(n: u32, spin: u32) -> Vec<u8> {
+        wat::parse_str(format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 1024)
+                (func (export "transform") (param $ptr i32) (param $len i32) (result i64)
+                    (local $i i32)
+                    (block (loop
+                        (br_if 1 (i32.ge_u (local.get $i) (i32.const {spin})))
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br 0)))
+                    (i64.or
+                        (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                        (i64.const {n}))))"#,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn runs_module_over_input() {
+        let transforms = Transforms::default();
+        let wasm = module(3, 0);
+        assert!(transforms.load("00", &wasm).is_err());
+        transforms.load(&module_hash(&wasm), &wasm).unwrap();
+        assert_eq!(
+            transforms.run(&module_hash(&wasm), "42.5 USD").unwrap(),
+            "42."
+        );
+    }
+
+    #[test]
+    fn limits_memory() {
+        let transforms = Transforms::default();
+        let declared = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 300)
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "transform") (param i32 i32) (result i64) i64.const 0))"#,
+        )
+        .unwrap();
+        assert!(transforms.load(&module_hash(&declared), &declared).is_err());
+
+        // returns as many bytes as it has memory pages after trying to grow by 300
+        let growing = wat::parse_str(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 1024)
+                (func (export "transform") (param $ptr i32) (param $len i32) (result i64)
+                    (drop (memory.grow (i32.const 300)))
+                    (i64.or
+                        (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                        (i64.extend_i32_u (memory.size)))))"#,
+        )
+        .unwrap();
+        transforms.load(&module_hash(&growing), &growing).unwrap();
+        assert_eq!(transforms.run(&module_hash(&growing), "42").unwrap(), "4");
+    }
+
+    #[test]
+    fn runs_out_of_fuel() {
+        let transforms = Transforms::default();
+        let wasm = module(1, u32::MAX);
+        transforms.load(&module_hash(&wasm), &wasm).unwrap();
+        assert!(transforms.run(&module_hash(&wasm), "1").is_err());
+    }
+}
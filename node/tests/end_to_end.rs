@@ -0,0 +1,840 @@
+use near_oracle_types::{AwaitError, OracleData, OracleEvent, OracleEventKind};
+use oracle_node::{
+    dry_run, metrics, Config, DryRunReport, JobStore, MockChain, MockStream, Node, PollReport,
+    SignerKey, Stage,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Hits = Arc<Mutex<HashMap<String, usize>>>;
+
+/// Serves `routes` (path, status, body) on a local port until the test process exits,
+/// counting requests per path. 429 responses carry `Retry-After: 120`, and 302 responses
+/// redirect to their body.
+fn stub_server_with_hits(routes: Vec<(&'static str, u16, &'static str)>) -> (String, Hits) {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let addr = format!("http://{}", server.server_addr().to_ip().unwrap());
+    let hits = Hits::default();
+    let counter = hits.clone();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            *counter
+                .lock()
+                .unwrap()
+                .entry(request.url().to_string())
+                .or_default() += 1;
+            let (status, body) = routes
+                .iter()
+                .find(|(path, _, _)| *path == request.url())
+                .map_or((404, "not found"), |(_, status, body)| (*status, *body));
+            let mut response = tiny_http::Response::from_string(body).with_status_code(status);
+            if status == 429 {
+                response.add_header(tiny_http::Header::from_bytes("Retry-After", "120").unwrap());
+            }
+            if status == 302 {
+                response.add_header(tiny_http::Header::from_bytes("Location", body).unwrap());
+            }
+            request.respond(response).unwrap();
+        }
+    });
+    (addr, hits)
+}
+
+fn stub_server(routes: Vec<(&'static str, u16, &'static str)>) -> String {
+    stub_server_with_hits(routes).0
+}
+
+fn request(id: &str, url: String, data: &str) -> OracleData {
+    OracleData {
+        id: id.to_string(),
+        url,
+        data: data.to_string(),
+        timestamp: 0,
+        executed: false,
+        return_value: None,
+        creator: "alice.testnet".parse().unwrap(),
+        attestation: None,
+        publisher: None,
+        evidence: None,
+        callback: None,
+        delivery: None,
+        deadline: None,
+        error: None,
+        executed_at: None,
+        encoding: None,
+        transform: None,
+    }
+}
+
+fn config(page_size: u64, max_requests: usize) -> Config {
+    config_with(page_size, max_requests, "")
+}
+
+fn config_with(page_size: u64, max_requests: usize, extra: &str) -> Config {
+    Config::from_toml(&format!(
+        r#"
+        [chain]
+        contract_id = "oracle.testnet"
+        operator_id = "operator.testnet"
+
+        [poll]
+        page_size = {}
+
+        [batch]
+        max_requests = {}
+
+        {}
+        "#,
+        page_size, max_requests, extra
+    ))
+    .unwrap()
+}
+
+#[test]
+fn fetches_pending_requests_and_submits_batches() {
+    let addr = stub_server(vec![
+        ("/price", 200, r#"{"near": {"usd": 3.1}}"#),
+        ("/plain", 200, "hello\n"),
+        ("/broken", 500, "oops"),
+    ]);
+    let mut done = request("done", format!("{}/price", addr), "{}");
+    done.executed = true;
+    let chain = MockChain::new(vec![
+        request("a", format!("{}/price", addr), r#"{"path": "/near/usd"}"#),
+        done,
+        request("b", format!("{}/plain", addr), "{}"),
+        request("c", format!("{}/broken", addr), "{}"),
+        request("d", format!("{}/price", addr), r#"{"path": "/near"}"#),
+    ]);
+    // pages of two and batches of two
+    let node = Node::new(config(2, 2), chain).unwrap();
+
+    let report = node.poll_once().unwrap();
+    assert_eq!(
+        report,
+        PollReport {
+            seen: 4,
+            fetched: 3,
+            fetch_failed: 1,
+            submitted: 3,
+            batches: 2,
+            ..Default::default()
+        }
+    );
+    let chain = node.chain();
+    assert_eq!(chain.get("a").unwrap().return_value.as_deref(), Some("3.1"));
+    assert_eq!(
+        chain.get("b").unwrap().return_value.as_deref(),
+        Some("hello")
+    );
+    assert_eq!(
+        chain.get("d").unwrap().return_value.as_deref(),
+        Some(r#"{"usd":3.1}"#)
+    );
+    assert!(!chain.get("c").unwrap().executed);
+    assert!(chain
+        .calls()
+        .iter()
+        .all(|call| call.method_name == "execute_oracle_batch"));
+
+    // only the failing request is retried
+    let report = node.poll_once().unwrap();
+    assert_eq!(
+        (report.seen, report.fetch_failed, report.batches),
+        (1, 1, 0)
+    );
+}
+
+#[test]
+fn failed_batch_is_retried_on_next_poll() {
+    let addr = stub_server(vec![("/plain", 200, "1")]);
+    let chain = MockChain::new(vec![
+        request("a", format!("{}/plain", addr), "{}"),
+        request("b", format!("{}/plain", addr), "{}"),
+    ]);
+    chain.fail_next_calls(1);
+    let node = Node::new(config_with(50, 10, "[store]\nresubmit_after_ms = 0"), chain).unwrap();
+
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.batch_failed, report.submitted), (1, 0));
+    assert!(!node.chain().get("a").unwrap().executed);
+
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.seen, report.submitted), (2, 2));
+    assert!(node.pending().unwrap().is_empty());
+}
+
+fn store_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oracle-node-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn restart_replays_fetched_values_and_never_resubmits_in_flight() {
+    let addr = stub_server(vec![("/price", 200, "2")]);
+    let dir = store_dir("restart");
+    let store_config = format!("[store]\npath = {:?}", dir);
+    {
+        // a previous run fetched "a" and submitted "b", then crashed
+        let store = JobStore::open(&config_with(50, 10, &store_config).store).unwrap();
+        store.fetched("a", "1", 0).unwrap();
+        store.fetched("b", "1", 0).unwrap();
+        store.submitted(&["b".to_string()], u64::MAX / 2).unwrap();
+    }
+    let chain = MockChain::new(vec![
+        request("a", format!("{}/price", addr), "{}"),
+        request("b", format!("{}/price", addr), "{}"),
+    ]);
+    let node = Node::new(config_with(50, 10, &store_config), chain).unwrap();
+
+    let report = node.poll_once().unwrap();
+    assert_eq!(
+        (
+            report.replayed,
+            report.fetched,
+            report.in_flight,
+            report.submitted
+        ),
+        (1, 0, 1, 1)
+    );
+    assert_eq!(
+        node.chain().get("a").unwrap().return_value.as_deref(),
+        Some("1")
+    );
+    assert!(!node.chain().get("b").unwrap().executed);
+
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.confirmed, report.submitted), (1, 0));
+    assert_eq!(
+        node.store().get("a").unwrap().unwrap().stage,
+        Stage::Confirmed
+    );
+    assert_eq!(node.store().get("b").unwrap().unwrap().submissions, 1);
+    drop(node);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn identical_urls_are_fetched_once() {
+    let (addr, hits) = stub_server_with_hits(vec![("/price", 200, r#"{"near": 3, "btc": 9}"#)]);
+    let chain = MockChain::new(vec![
+        request("a", format!("{}/price", addr), r#"{"path": "/near"}"#),
+        request("b", format!("{}/price", addr), r#"{"path": "/btc"}"#),
+        request("c", format!("{}/price", addr), "{}"),
+    ]);
+    let node = Node::new(config(50, 10), chain).unwrap();
+
+    let report = node.poll_once().unwrap();
+    assert_eq!(report.submitted, 3);
+    assert_eq!(hits.lock().unwrap()["/price"], 1);
+    assert_eq!(
+        node.chain().get("b").unwrap().return_value.as_deref(),
+        Some("9")
+    );
+}
+
+#[test]
+fn host_limits_and_retry_after_defer_fetches() {
+    let (addr, hits) = stub_server_with_hits(vec![
+        ("/a", 200, "1"),
+        ("/b", 200, "2"),
+        ("/busy", 429, "slow down"),
+    ]);
+    // the stub listens on 127.0.0.1; limit it to a single request
+    let chain = MockChain::new(vec![
+        request("a", format!("{}/a", addr), "{}"),
+        request("b", format!("{}/b", addr), "{}"),
+    ]);
+    let limits = "[fetch.hosts.\"127.0.0.1\"]\nrate_per_sec = 0.001\nburst = 1";
+    let node = Node::new(config_with(50, 10, limits), chain).unwrap();
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.fetched, report.rate_limited), (1, 1));
+    assert_eq!(hits.lock().unwrap().get("/b"), None);
+
+    // a 429 blocks the host for its Retry-After
+    let chain = MockChain::new(vec![
+        request("busy", format!("{}/busy", addr), "{}"),
+        request("a", format!("{}/a", addr), "{}"),
+    ]);
+    let node = Node::new(config(50, 10), chain).unwrap();
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.fetched, report.rate_limited), (0, 2));
+    let report = node.poll_once().unwrap();
+    assert_eq!(report.rate_limited, 2);
+    assert_eq!(hits.lock().unwrap()["/busy"], 1);
+    assert_eq!(hits.lock().unwrap()["/a"], 1);
+}
+
+#[test]
+fn secrets_are_sent_only_to_allowed_hosts_and_never_submitted() {
+    let (addr, hits) = stub_server_with_hits(vec![
+        ("/price?key=s3cret", 200, "7"),
+        ("/echo", 200, "your key is s3cret"),
+    ]);
+    let dir = store_dir("secrets");
+    std::fs::create_dir_all(&dir).unwrap();
+    let secrets = dir.join("secrets.toml");
+    std::fs::write(
+        &secrets,
+        r#"
+        [api_key]
+        value = "s3cret"
+        hosts = ["127.0.0.1"]
+
+        [other_key]
+        value = "elsewhere"
+        hosts = ["api.example.com"]
+        "#,
+    )
+    .unwrap();
+    let chain = MockChain::new(vec![
+        request(
+            "a",
+            format!("{}/price?key={{{{secret:api_key}}}}", addr),
+            "{}",
+        ),
+        request(
+            "b",
+            format!("{}/price?key={{{{secret:other_key}}}}", addr),
+            "{}",
+        ),
+        request(
+            "c",
+            format!("{}/echo", addr),
+            r#"{"headers": {"x-api-key": "{{secret:api_key}}"}}"#,
+        ),
+    ]);
+    let extra = format!("[secrets]\npath = {:?}", secrets);
+    let node = Node::new(config_with(50, 10, &extra), chain).unwrap();
+
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.submitted, report.fetch_failed), (1, 2));
+    assert_eq!(
+        node.chain().get("a").unwrap().return_value.as_deref(),
+        Some("7")
+    );
+    assert_eq!(node.chain().get("c").unwrap().return_value, None);
+    let hits = hits.lock().unwrap();
+    assert_eq!(hits.len(), 2);
+    assert!(hits.keys().all(|path| !path.contains("elsewhere")));
+}
+
+#[test]
+fn redirects_never_carry_secrets_to_another_host() {
+    let (other, other_hits) = stub_server_with_hits(vec![("/steal", 200, "7")]);
+    // the same server, under a host the secret is not allowed for
+    let other = other.replace("127.0.0.1", "localhost");
+    let location: &'static str = Box::leak(format!("{}/steal", other).into_boxed_str());
+    let addr = stub_server(vec![("/moved", 302, location)]);
+    let dir = store_dir("redirect");
+    std::fs::create_dir_all(&dir).unwrap();
+    let secrets = dir.join("secrets.toml");
+    std::fs::write(
+        &secrets,
+        "[api_key]\nvalue = \"s3cret\"\nhosts = [\"127.0.0.1\"]\n",
+    )
+    .unwrap();
+    let chain = MockChain::new(vec![request(
+        "a",
+        format!("{}/moved", addr),
+        r#"{"headers": {"x-api-key": "{{secret:api_key}}"}}"#,
+    )]);
+    let extra = format!("[secrets]\npath = {:?}", secrets);
+    let node = Node::new(config_with(50, 10, &extra), chain).unwrap();
+
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.fetch_failed, report.submitted), (1, 0));
+    assert!(other_hits.lock().unwrap().is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+fn get(url: &str) -> (u16, String) {
+    match ureq::get(url).call() {
+        Ok(response) => (response.status(), response.into_string().unwrap()),
+        Err(ureq::Error::Status(status, response)) => (status, response.into_string().unwrap()),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+#[test]
+fn metrics_and_health_endpoints() {
+    let addr = stub_server(vec![("/price", 200, "5"), ("/broken", 500, "oops")]);
+    let mut fresh = request("a", format!("{}/price", addr), "{}");
+    fresh.timestamp = now_ms();
+    let chain = MockChain::new(vec![fresh]);
+    let node = Node::new(config(50, 10), chain).unwrap();
+    let metrics = format!(
+        "http://{}",
+        metrics::serve("127.0.0.1:0", node.metrics().clone()).unwrap()
+    );
+    assert_eq!(get(&format!("{}/healthz", metrics)).0, 503);
+
+    node.poll_once().unwrap();
+    assert_eq!(
+        get(&format!("{}/healthz", metrics)),
+        (200, "ok\n".to_string())
+    );
+    let (status, text) = get(&format!("{}/metrics", metrics));
+    assert_eq!(status, 200);
+    for line in [
+        "oracle_node_queue_depth 1",
+        "oracle_node_fetch_duration_seconds_count{host=\"127.0.0.1\"} 1",
+        "oracle_node_batches_total{outcome=\"success\"} 1",
+        "oracle_node_submitted_requests_total 1",
+        "oracle_node_batch_gas_burnt_total 15000000000000",
+        "oracle_node_fulfilment_lag_seconds_count 1",
+    ] {
+        assert!(text.lines().any(|l| l == line), "missing {}", line);
+    }
+
+    // a request that cannot be fetched is counted apart instead of holding back the lag
+    node.chain()
+        .push(request("old", format!("{}/broken", addr), "{}"));
+    node.poll_once().unwrap();
+    assert_eq!(get(&format!("{}/healthz", metrics)).0, 200);
+    let (_, text) = get(&format!("{}/metrics", metrics));
+    assert!(text
+        .lines()
+        .any(|l| l == "oracle_node_unfetchable_requests 1"));
+
+    // a value that does not land leaves the node behind
+    node.chain()
+        .push(request("stuck", format!("{}/price", addr), "{}"));
+    node.chain().fail_next_calls(1);
+    node.poll_once().unwrap();
+    let (status, reason) = get(&format!("{}/healthz", metrics));
+    assert_eq!(status, 503);
+    assert!(reason.starts_with("oldest unfulfilled request"));
+}
+
+/// Answers GraphQL at `/graphql` and JSON-RPC at `/rpc` from the posted bodies.
+fn rpc_stub_server() -> String {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let addr = format!("http://{}", server.server_addr().to_ip().unwrap());
+    thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let body: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
+            let response = match request.url() {
+                "/graphql" if body["variables"]["id"] == "near" => {
+                    serde_json::json!({"data": {"token": {"price": 3.25}}})
+                }
+                "/graphql" => {
+                    serde_json::json!({"data": null, "errors": [{"message": "unknown token"}]})
+                }
+                "/rpc" if body["method"] == "eth_blockNumber" => {
+                    serde_json::json!({"jsonrpc": "2.0", "id": body["id"], "result": "0x10"})
+                }
+                _ => {
+                    serde_json::json!({"jsonrpc": "2.0", "id": body["id"], "error": {"code": -32601, "message": "method not found"}})
+                }
+            };
+            request
+                .respond(tiny_http::Response::from_string(response.to_string()))
+                .unwrap();
+        }
+    });
+    addr
+}
+
+#[test]
+fn graphql_and_jsonrpc_sources() {
+    let addr = rpc_stub_server();
+    let query = r#""query": "query($id: ID!) { token(id: $id) { price } }""#;
+    let chain = MockChain::new(vec![
+        request(
+            "gql",
+            format!("{}/graphql", addr),
+            &format!(
+                r#"{{"source_type": "graphql", {}, "variables": {{"id": "near"}}, "path": "/token/price"}}"#,
+                query
+            ),
+        ),
+        request(
+            "gql-error",
+            format!("{}/graphql", addr),
+            &format!(
+                r#"{{"source_type": "graphql", {}, "variables": {{"id": "doge"}}}}"#,
+                query
+            ),
+        ),
+        request(
+            "rpc",
+            format!("{}/rpc", addr),
+            r#"{"source_type": "jsonrpc", "method": "eth_blockNumber"}"#,
+        ),
+        request(
+            "rpc-error",
+            format!("{}/rpc", addr),
+            r#"{"source_type": "jsonrpc", "method": "eth_nope"}"#,
+        ),
+    ]);
+    let node = Node::new(config(50, 10), chain).unwrap();
+
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.submitted, report.fetch_failed), (2, 2));
+    let chain = node.chain();
+    assert_eq!(
+        chain.get("gql").unwrap().return_value.as_deref(),
+        Some("3.25")
+    );
+    assert_eq!(
+        chain.get("rpc").unwrap().return_value.as_deref(),
+        Some("0x10")
+    );
+    assert!(!chain.get("gql-error").unwrap().executed);
+    assert!(!chain.get("rpc-error").unwrap().executed);
+}
+
+/// Returns the input up to its first space.
+const FIRST_WORD: &str = r#"(module
+    (memory (export "memory") 1)
+    (func (export "alloc") (param i32) (result i32) i32.const 1024)
+    (func (export "transform") (param $ptr i32) (param $len i32) (result i64)
+        (local $i i32)
+        (block (loop
+            (br_if 1 (i32.ge_u (local.get $i) (local.get $len)))
+            (br_if 1 (i32.eq
+                (i32.load8_u (i32.add (local.get $ptr) (local.get $i)))
+                (i32.const 32)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br 0)))
+        (i64.or
+            (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+            (i64.extend_i32_u (local.get $i)))))"#;
+
+#[test]
+fn operators_running_the_same_transform_agree() {
+    let addr = stub_server(vec![("/quote", 200, "42.5 USD")]);
+    let module = wat::parse_str(FIRST_WORD).unwrap();
+    let values: Vec<_> = (0..2)
+        .map(|_| {
+            let chain = MockChain::default();
+            let mut quote = request("quote", format!("{}/quote", addr), "{}");
+            quote.transform = Some(chain.register_transform(module.clone()));
+            chain.push(quote);
+            let mut missing = request("missing", format!("{}/quote", addr), "{}");
+            missing.transform = Some("00".repeat(32));
+            chain.push(missing);
+            let node = Node::new(config(50, 10), chain).unwrap();
+
+            let report = node.poll_once().unwrap();
+            assert_eq!((report.submitted, report.fetch_failed), (1, 1));
+            node.chain().get("quote").unwrap().return_value
+        })
+        .collect();
+    assert_eq!(values, vec![Some("42.5".to_string()); 2]);
+}
+
+fn key_file(dir: &std::path::Path, seed: u8) -> PathBuf {
+    let path = dir.join(format!("key-{}.json", seed));
+    std::fs::write(
+        &path,
+        serde_json::json!({
+            "account_id": "operator.testnet",
+            "public_key": "",
+            "private_key": format!("ed25519:{}", bs58::encode([seed; 32]).into_string()),
+        })
+        .to_string(),
+    )
+    .unwrap();
+    path
+}
+
+#[test]
+fn submitter_spreads_batches_over_keys_and_tracks_finality() {
+    let addr = stub_server(vec![("/plain", 200, "1")]);
+    let dir = store_dir("keys");
+    std::fs::create_dir_all(&dir).unwrap();
+    let keys = [key_file(&dir, 1), key_file(&dir, 2)];
+    let chain = MockChain::new(
+        ["a", "b", "c", "d"]
+            .iter()
+            .map(|id| request(id, format!("{}/plain", addr), "{}"))
+            .collect(),
+    );
+    let extra = format!(
+        "[submitter]\nkey_files = {:?}\n[store]\nresubmit_after_ms = 600000",
+        keys
+    );
+    let node = Node::new(config_with(50, 1, &extra), chain).unwrap();
+
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.batches, report.submitted), (4, 4));
+    assert_eq!(node.submitter().unwrap().in_flight().len(), 4);
+    // not final yet
+    node.chain()
+        .push(request("e", format!("{}/plain", addr), "{}"));
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.finalized, report.submitted), (0, 1));
+
+    // another client used the first key, and one of the next transactions fails
+    let first = SignerKey::load(&keys[0]).unwrap();
+    node.chain().use_nonce(&first.public_key(), 1_000);
+    node.chain().fail_next_calls(1);
+    node.chain()
+        .push(request("f", format!("{}/plain", addr), "{}"));
+    node.chain()
+        .push(request("g", format!("{}/plain", addr), "{}"));
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.submitted, report.batch_failed), (2, 0));
+
+    node.chain().finalize();
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.finalized, report.tx_failed), (6, 1));
+    // the failed request was submitted again in the same poll
+    assert_eq!((report.replayed, report.submitted), (1, 1));
+    node.chain().finalize();
+    let report = node.poll_once().unwrap();
+    assert_eq!(report.finalized, 1);
+    assert!(node.pending().unwrap().is_empty());
+    assert!(node.submitter().unwrap().in_flight().is_empty());
+}
+
+#[test]
+fn resubmits_only_once_the_chain_shows_nothing_landed() {
+    let addr = stub_server(vec![("/plain", 200, "1")]);
+    let dir = store_dir("recheck");
+    std::fs::create_dir_all(&dir).unwrap();
+    let chain = MockChain::new(vec![request("a", format!("{}/plain", addr), "{}")]);
+    let extra = format!(
+        "[submitter]\nkey_files = {:?}\n[store]\nresubmit_after_ms = 0",
+        [key_file(&dir, 1)]
+    );
+    let node = Node::new(config_with(50, 10, &extra), chain).unwrap();
+
+    // the transaction fails, but is not final yet
+    node.chain().fail_next_calls(1);
+    assert_eq!(node.poll_once().unwrap().submitted, 1);
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.in_flight, report.submitted), (1, 0));
+
+    node.chain().finalize();
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.replayed, report.submitted), (1, 1));
+    assert!(node.chain().get("a").unwrap().executed);
+
+    // a transaction the chain never saw is resubmitted once the request is not executed
+    node.chain()
+        .push(request("b", format!("{}/plain", addr), "{}"));
+    node.store().fetched("b", "1", 0).unwrap();
+    node.store().submitted(&["b".to_string()], 0).unwrap();
+    node.store().sent(&["b".to_string()], "tx-lost").unwrap();
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.replayed, report.submitted), (1, 1));
+    assert!(node
+        .chain()
+        .views()
+        .contains(&"get_queued_data_by_id".to_string()));
+    assert!(node.pending().unwrap().is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn requests_the_contract_skips_are_not_confirmed() {
+    use near_oracle_types::encoding::{ResultEncoding, ValueType};
+
+    let addr = stub_server(vec![("/plain", 200, "1"), ("/word", 200, "one")]);
+    let dir = store_dir("skipped");
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut amount = request("amount", format!("{}/word", addr), "{}");
+    amount.encoding = Some(ResultEncoding::Borsh {
+        value_type: ValueType::U128,
+    });
+    let chain = MockChain::new(vec![request("a", format!("{}/plain", addr), "{}"), amount]);
+    let extra = format!(
+        "[submitter]\nkey_files = {:?}\n[store]\nresubmit_after_ms = 0",
+        [key_file(&dir, 1)]
+    );
+    let node = Node::new(config_with(50, 10, &extra), chain).unwrap();
+
+    assert_eq!(node.poll_once().unwrap().submitted, 2);
+    node.chain().finalize();
+    // "one" is not a U128, so the batch lands without executing that request
+    let report = node.poll_once().unwrap();
+    assert_eq!(
+        (report.finalized, report.skipped, report.submitted),
+        (2, 1, 0)
+    );
+    assert_eq!(
+        node.store().get("a").unwrap().unwrap().stage,
+        Stage::Confirmed
+    );
+    assert_eq!(
+        node.store().get("amount").unwrap().unwrap().stage,
+        Stage::Skipped
+    );
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.skipped, report.submitted), (1, 0));
+    assert!(!node.chain().get("amount").unwrap().executed);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn dry_run_reports_without_submitting() {
+    let addr = stub_server(vec![
+        ("/price", 200, r#"{"near": {"usd": 3.1}}"#),
+        ("/broken", 500, "oops"),
+    ]);
+    let executed = |id: &str, value: &str| {
+        let mut oracle_data = request(id, format!("{}/price", addr), r#"{"path": "/near/usd"}"#);
+        oracle_data.executed = true;
+        oracle_data.return_value = Some(value.to_string());
+        oracle_data
+    };
+    let chain = MockChain::new(vec![
+        request("a", format!("{}/price", addr), r#"{"path": "/near/usd"}"#),
+        request("b", format!("{}/broken", addr), "{}"),
+        executed("same", "3.1"),
+        executed("other", "3.0"),
+    ]);
+    let node = Node::new(config(50, 10), chain).unwrap();
+
+    let mut out = vec![];
+    let report = dry_run(&node, &mut out).unwrap();
+    assert_eq!(
+        report,
+        DryRunReport {
+            would_submit: 1,
+            batches: 1,
+            fetch_failed: 1,
+            matched: 1,
+            mismatched: 1,
+        }
+    );
+    let lines: Vec<serde_json::Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(
+        (&lines[0]["id"], &lines[0]["value"], &lines[0]["batch"]),
+        (&"a".into(), &"3.1".into(), &0.into())
+    );
+    assert!(lines[1]["error"].is_string());
+    assert_eq!(lines[2]["matches"], true);
+    assert_eq!(
+        (&lines[3]["submitted"], &lines[3]["matches"]),
+        (&"3.0".into(), &false.into())
+    );
+    assert!((lines[3]["delta"].as_f64().unwrap() - 0.1).abs() < 1e-9);
+
+    // nothing was sent or recorded
+    assert!(node.chain().calls().is_empty());
+    assert!(node.store().jobs().unwrap().is_empty());
+    assert!(!node.chain().get("a").unwrap().executed);
+}
+
+#[test]
+fn follows_queue_from_events_and_polls_on_gaps() {
+    let addr = stub_server(vec![("/plain", 200, "1")]);
+    let oracle: near_sdk::AccountId = "oracle.testnet".parse().unwrap();
+    let created =
+        |id: &str| OracleEvent::new(OracleEventKind::OracleCreated, vec![id.to_string()]).to_log();
+    let chain = MockChain::new(vec![request("a", format!("{}/plain", addr), "{}")]);
+    let stream = MockStream::default();
+    let node = Node::new(config(50, 10), chain)
+        .unwrap()
+        .with_stream(stream.clone());
+    let full_reads = |node: &Node<MockChain>| {
+        node.chain()
+            .views()
+            .iter()
+            .filter(|method| *method == "get_queued_data")
+            .count()
+    };
+
+    // the queue is read in full once, at start
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.submitted, report.resynced), (1, false));
+    assert_eq!(full_reads(&node), 1);
+
+    // then followed from the contract's events
+    node.chain()
+        .push(request("b", format!("{}/plain", addr), "{}"));
+    let mut logs = node.chain().take_logs();
+    logs.push(created("b"));
+    stream.push(&oracle, logs);
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.confirmed, report.seen, report.submitted), (1, 1, 1));
+    assert_eq!(full_reads(&node), 1);
+
+    // events logged by another contract are ignored
+    node.chain()
+        .push(request("c", format!("{}/plain", addr), "{}"));
+    stream.push(&"fake.testnet".parse().unwrap(), vec![created("c")]);
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.in_flight, report.submitted), (1, 0));
+    assert!(!node.chain().get("c").unwrap().executed);
+
+    // a missed block falls back to reading the whole queue
+    stream.skip();
+    stream.push(&oracle, node.chain().take_logs());
+    let report = node.poll_once().unwrap();
+    assert_eq!(
+        (report.resynced, report.seen, report.submitted),
+        (true, 1, 1)
+    );
+    assert_eq!(full_reads(&node), 2);
+    assert!(node.chain().get("c").unwrap().executed);
+
+    stream.push(&oracle, node.chain().take_logs());
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.resynced, report.confirmed), (false, 1));
+    assert_eq!(full_reads(&node), 2);
+}
+
+#[test]
+fn requests_past_their_deadline_are_expired() {
+    let addr = stub_server(vec![("/plain", 200, "1")]);
+    let oracle: near_sdk::AccountId = "oracle.testnet".parse().unwrap();
+    let mut late = request("late", format!("{}/plain", addr), "{}");
+    late.deadline = Some(now_ms() - 1);
+    let mut awaited = request("awaited", format!("{}/plain", addr), "{}");
+    awaited.deadline = Some(now_ms() + 60_000);
+    let chain = MockChain::new(vec![late, awaited]);
+    chain.fail_next_calls(1);
+    let stream = MockStream::default();
+    let node = Node::new(config(50, 10), chain)
+        .unwrap()
+        .with_stream(stream.clone());
+
+    // a failed expiry is kept in the followed queue and tried again
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.expired, report.seen, report.submitted), (0, 1, 1));
+    assert!(node.chain().get("late").unwrap().error.is_none());
+
+    stream.push(&oracle, node.chain().take_logs());
+    let report = node.poll_once().unwrap();
+    assert_eq!((report.expired, report.seen), (1, 0));
+    assert_eq!(
+        node.chain().get("late").unwrap().error,
+        Some(AwaitError::TimedOut)
+    );
+    assert!(node.chain().get("awaited").unwrap().executed);
+
+    // once its expiry event is applied the request is not expired again
+    stream.push(&oracle, node.chain().take_logs());
+    let report = node.poll_once().unwrap();
+    assert_eq!(report.expired, 0);
+    assert_eq!(
+        node.chain()
+            .calls()
+            .iter()
+            .filter(|call| call.method_name == "expire_oracle")
+            .count(),
+        2
+    );
+}
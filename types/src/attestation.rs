@@ -0,0 +1,14 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::PublicKey;
+
+/// Signature of a registered data provider over a fulfilment and its evidence, letting any
+/// account relay it.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Attestation {
+    pub public_key: PublicKey,
+    /// Time (ms) the provider observed the value.
+    pub timestamp: u64,
+    pub signature: Base64VecU8,
+}
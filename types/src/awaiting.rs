@@ -0,0 +1,17 @@
+use crate::*;
+
+/// Why an awaited request was resumed without a value.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AwaitError {
+    TimedOut,
+}
+
+/// Result of polling a request with `loop_await_entry`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AwaitOutcome {
+    Fulfilled(String),
+    Failed(String),
+    TimedOut { iterations: u32 },
+}
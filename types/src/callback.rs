@@ -0,0 +1,39 @@
+use crate::*;
+use near_sdk::json_types::U64;
+use near_sdk::{require, AccountId, Gas};
+
+pub const MAX_CALLBACK_GAS: Gas = Gas(100_000_000_000_000);
+
+/// Consumer method called with `{ "id", "value" }` once a request is fulfilled, or with
+/// `{ "id", "value": null, "error" }` when an awaited request expired (or the Borsh
+/// equivalent for requests created with `ResultEncoding::Borsh`).
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Callback {
+    pub contract_id: AccountId,
+    pub method: String,
+    pub gas: U64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum DeliveryStatus {
+    /// A delivery is in flight; `attempts` counts it.
+    Pending {
+        attempts: u32,
+    },
+    Delivered,
+    DeliveryFailed {
+        attempts: u32,
+    },
+}
+
+impl Callback {
+    pub fn assert_valid(&self) {
+        require!(!self.method.is_empty(), "ERR_INVALID_CALLBACK_METHOD");
+        require!(
+            self.gas.0 <= MAX_CALLBACK_GAS.0,
+            "ERR_CALLBACK_GAS_TOO_HIGH"
+        );
+    }
+}
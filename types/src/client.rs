@@ -0,0 +1,193 @@
+//! Builds function-call arguments for the oracle contract and parses its view results, for
+//! use with any RPC library.
+
+use crate::*;
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::serde_json::{self, json, Value};
+use near_sdk::AccountId;
+
+pub const DEFAULT_CALL_GAS: u64 = 100_000_000_000_000;
+pub const MAX_CALL_GAS: u64 = 300_000_000_000_000;
+
+/// A change call to sign and send.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionCall {
+    pub receiver_id: AccountId,
+    pub method_name: String,
+    /// JSON encoded arguments.
+    pub args: Vec<u8>,
+    pub gas: u64,
+    pub deposit: u128,
+}
+
+/// A view call; its result is read with [`parse_view`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ViewCall {
+    pub contract_id: AccountId,
+    pub method_name: String,
+    pub args: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct OracleClient {
+    pub contract_id: AccountId,
+}
+
+impl OracleClient {
+    pub fn new(contract_id: AccountId) -> Self {
+        Self { contract_id }
+    }
+
+    fn call(&self, method_name: &str, args: Value, gas: u64, deposit: u128) -> FunctionCall {
+        FunctionCall {
+            receiver_id: self.contract_id.clone(),
+            method_name: method_name.to_string(),
+            args: args.to_string().into_bytes(),
+            gas,
+            deposit,
+        }
+    }
+    fn view(&self, method_name: &str, args: Value) -> ViewCall {
+        ViewCall {
+            contract_id: self.contract_id.clone(),
+            method_name: method_name.to_string(),
+            args: args.to_string().into_bytes(),
+        }
+    }
+
+    /// `deposit` must cover the contract's `fee_per_call`.
+    pub fn create_oracle(&self, request: &OracleRequest, deposit: u128) -> FunctionCall {
+        self.call(
+            "create_oracle",
+            serde_json::to_value(request).unwrap(),
+            DEFAULT_CALL_GAS,
+            deposit,
+        )
+    }
+    pub fn execute_oracle(&self, id: &str, return_value: &str) -> FunctionCall {
+        self.call(
+            "execute_oracle",
+            json!({ "id": id, "return_value": return_value }),
+            DEFAULT_CALL_GAS,
+            0,
+        )
+    }
+    /// `results` pairs request ids with their return values.
+    pub fn execute_oracle_batch(&self, results: &[(String, String)], gas: u64) -> FunctionCall {
+        let (ids, return_values): (Vec<_>, Vec<_>) = results.iter().cloned().unzip();
+        self.call(
+            "execute_oracle_batch",
+            json!({ "ids": ids, "return_values": return_values }),
+            gas,
+            0,
+        )
+    }
+    /// Resumes the awaiter of a request past its deadline with a timeout; `gas` must cover
+    /// the request's callback.
+    pub fn expire_oracle(&self, id: &str, gas: u64) -> FunctionCall {
+        self.call("expire_oracle", json!({ "id": id }), gas, 0)
+    }
+    pub fn get_queued_data(&self, from_index: Option<u64>, limit: Option<u64>) -> ViewCall {
+        self.view(
+            "get_queued_data",
+            json!({ "from_index": from_index, "limit": limit }),
+        )
+    }
+    pub fn get_queued_data_by_id(&self, id: &str) -> ViewCall {
+        self.view("get_queued_data_by_id", json!({ "id": id }))
+    }
+    pub fn get_queued_data_by_executed(
+        &self,
+        executed: bool,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> ViewCall {
+        self.view(
+            "get_queued_data_by_executed",
+            json!({ "executed": executed, "from_index": from_index, "limit": limit }),
+        )
+    }
+    pub fn get_transform(&self, hash: &str) -> ViewCall {
+        self.view("get_transform", json!({ "hash": hash }))
+    }
+    pub fn storage_balance_of(&self, account_id: &AccountId) -> ViewCall {
+        self.view("storage_balance_of", json!({ "account_id": account_id }))
+    }
+}
+
+/// Parses the raw JSON result of a view call, e.g. `parse_view::<Vec<OracleData>>(&bytes)`.
+pub fn parse_view<T: DeserializeOwned>(result: &[u8]) -> Result<T, serde_json::Error> {
+    serde_json::from_slice(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> OracleClient {
+        OracleClient::new("oracle.testnet".parse().unwrap())
+    }
+
+    #[test]
+    fn create_oracle_args_omit_unset_options() {
+        let call = client().create_oracle(
+            &OracleRequest {
+                url: "https://example.com".to_string(),
+                data: "{}".to_string(),
+                callback: None,
+                encoding: None,
+            },
+            1,
+        );
+        assert_eq!(call.method_name, "create_oracle");
+        assert_eq!(
+            parse_view::<Value>(&call.args).unwrap(),
+            json!({ "url": "https://example.com", "data": "{}" })
+        );
+    }
+
+    #[test]
+    fn batch_args_keep_ids_and_values_aligned() {
+        let call = client().execute_oracle_batch(
+            &[
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "2".to_string()),
+            ],
+            MAX_CALL_GAS,
+        );
+        assert_eq!(
+            parse_view::<Value>(&call.args).unwrap(),
+            json!({ "ids": ["a", "b"], "return_values": ["1", "2"] })
+        );
+    }
+
+    #[test]
+    fn parses_queued_data_view() {
+        let result = json!([{
+            "id": "a",
+            "url": "https://example.com",
+            "data": "{}",
+            "timestamp": 1,
+            "executed": false,
+            "return_value": null,
+            "creator": "alice.testnet",
+            "attestation": null,
+            "publisher": null,
+            "evidence": null,
+            "callback": null,
+            "delivery": null,
+            "deadline": null,
+            "error": null,
+            "executed_at": null,
+            "encoding": { "Borsh": { "value_type": "U128" } },
+        }]);
+        let queued: Vec<OracleData> = parse_view(result.to_string().as_bytes()).unwrap();
+        assert_eq!(queued[0].id, "a");
+        assert_eq!(
+            queued[0].result_encoding(),
+            ResultEncoding::Borsh {
+                value_type: ValueType::U128
+            }
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use crate::*;
+use near_sdk::AccountId;
+
+pub type Url = String;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OracleData {
+    pub id: String,
+    pub url: Url,
+    pub data: String,
+    pub timestamp: u64,
+    pub executed: bool,
+    pub return_value: Option<String>,
+    pub creator: AccountId,
+    pub attestation: Option<Attestation>,
+    pub publisher: Option<String>,
+    pub evidence: Option<ResponseEvidence>,
+    pub callback: Option<Callback>,
+    /// Callback delivery state, `None` when there is no callback or nothing was delivered yet.
+    pub delivery: Option<DeliveryStatus>,
+    /// Time (ms) after which an awaited request can no longer be fulfilled.
+    pub deadline: Option<u64>,
+    pub error: Option<AwaitError>,
+    /// Time (ms) the result was last written.
+    pub executed_at: Option<u64>,
+    /// Encoding of the delivered result, `None` meaning JSON.
+    pub encoding: Option<ResultEncoding>,
+    /// Hex SHA-256 of the WASM module, set by `"transform"` in `data`, that turns the
+    /// response into the value. See [`transform`](crate::transform).
+    pub transform: Option<String>,
+}
+
+/// Arguments of `create_oracle`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OracleRequest {
+    pub url: Url,
+    /// JSON document describing what to read from the response.
+    pub data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback: Option<Callback>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<ResultEncoding>,
+}
+
+/// Outcome of a request as exposed to consumer contracts, independent of `OracleData`
+/// internals.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OracleResult {
+    pub id: String,
+    pub executed: bool,
+    pub value: Option<String>,
+    /// Time (ms) the value was written.
+    pub executed_at: Option<u64>,
+    pub error: Option<AwaitError>,
+}
+
+impl From<OracleData> for OracleResult {
+    fn from(oracle_data: OracleData) -> Self {
+        Self {
+            id: oracle_data.id,
+            executed: oracle_data.executed,
+            value: oracle_data.return_value,
+            executed_at: oracle_data.executed_at,
+            error: oracle_data.error,
+        }
+    }
+}
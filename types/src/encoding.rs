@@ -0,0 +1,80 @@
+use crate::*;
+use near_sdk::serde_json::json;
+
+/// Type a `return_value` is parsed into for Borsh delivery.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ValueType {
+    String,
+    Bool,
+    U64,
+    U128,
+    I128,
+}
+
+/// How a request's result is encoded for its callback and `get_encoded_result`.
+///
+/// `Json` (the default) sends `{ "id", "value", "error"? }` with `value` as a string.
+/// `Borsh` sends the Borsh encoding of `(id: String, value: Option<T>, error:
+/// Option<AwaitError>)` where `T` is `value_type`, so a consumer can declare its callback
+/// arguments with `#[serializer(borsh)]` and skip JSON parsing.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ResultEncoding {
+    Json,
+    Borsh { value_type: ValueType },
+}
+
+impl ValueType {
+    /// Borsh encoding of `value` parsed as this type, `None` if it does not parse.
+    pub fn borsh_encode(&self, value: &str) -> Option<Vec<u8>> {
+        match self {
+            ValueType::String => value.try_to_vec().ok(),
+            ValueType::Bool => value.parse::<bool>().ok()?.try_to_vec().ok(),
+            ValueType::U64 => value.parse::<u64>().ok()?.try_to_vec().ok(),
+            ValueType::U128 => value.parse::<u128>().ok()?.try_to_vec().ok(),
+            ValueType::I128 => value.parse::<i128>().ok()?.try_to_vec().ok(),
+        }
+    }
+}
+
+impl OracleData {
+    pub fn result_encoding(&self) -> ResultEncoding {
+        self.encoding.unwrap_or(ResultEncoding::Json)
+    }
+    /// Result payload in the request's encoding, as passed to its callback.
+    pub fn encoded_result(&self) -> Vec<u8> {
+        match self.result_encoding() {
+            ResultEncoding::Json => {
+                let mut args = json!({
+                    "id": self.id,
+                    "value": self.return_value,
+                });
+                if let Some(error) = &self.error {
+                    args["error"] = json!(error);
+                }
+                args.to_string().into_bytes()
+            }
+            ResultEncoding::Borsh { value_type } => {
+                let mut args = self.id.try_to_vec().unwrap();
+                match &self.return_value {
+                    Some(value) => {
+                        args.push(1);
+                        args.extend(
+                            value_type
+                                .borsh_encode(value)
+                                .expect("ERR_INVALID_VALUE_FOR_ENCODING"),
+                        );
+                    }
+                    None => args.push(0),
+                }
+                args.extend(self.error.try_to_vec().unwrap());
+                args
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! NEP-297 events the contract logs when a queued request changes, so operators can follow
+//! the queue from receipt logs instead of paging through `get_queued_data`.
+//!
+//! Events only carry request ids; the request itself is read with `get_queued_data_by_id`.
+
+use crate::*;
+use near_sdk::serde_json;
+
+pub const EVENT_STANDARD: &str = "near-oracle";
+pub const EVENT_VERSION: &str = "1.0.0";
+const EVENT_PREFIX: &str = "EVENT_JSON:";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum OracleEventKind {
+    OracleCreated,
+    OracleExecuted,
+    /// An awaited request passed its deadline.
+    OracleExpired,
+    OracleDeleted,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OracleEventData {
+    pub id: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OracleEvent {
+    pub standard: String,
+    pub version: String,
+    pub event: OracleEventKind,
+    pub data: Vec<OracleEventData>,
+}
+
+impl OracleEvent {
+    pub fn new(event: OracleEventKind, ids: Vec<String>) -> Self {
+        Self {
+            standard: EVENT_STANDARD.to_string(),
+            version: EVENT_VERSION.to_string(),
+            event,
+            data: ids.into_iter().map(|id| OracleEventData { id }).collect(),
+        }
+    }
+
+    /// The `EVENT_JSON:` log line.
+    pub fn to_log(&self) -> String {
+        format!("{}{}", EVENT_PREFIX, serde_json::to_string(self).unwrap())
+    }
+
+    /// Parses a log line, giving `None` for anything but an event of this standard.
+    pub fn from_log(log: &str) -> Option<Self> {
+        let event: Self = serde_json::from_str(log.strip_prefix(EVENT_PREFIX)?).ok()?;
+        (event.standard == EVENT_STANDARD).then_some(event)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.data.iter().map(|data| data.id.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_event_logs() {
+        let event = OracleEvent::new(OracleEventKind::OracleCreated, vec!["a".to_string()]);
+        let log = event.to_log();
+        assert_eq!(
+            log,
+            r#"EVENT_JSON:{"standard":"near-oracle","version":"1.0.0","event":"oracle_created","data":[{"id":"a"}]}"#
+        );
+        assert_eq!(OracleEvent::from_log(&log), Some(event));
+        assert_eq!(OracleEvent::from_log("a"), None);
+        assert_eq!(
+            OracleEvent::from_log(
+                r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[]}"#
+            ),
+            None
+        );
+    }
+}
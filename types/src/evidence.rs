@@ -0,0 +1,15 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+
+/// What the operator saw when fetching a request, stored next to its `return_value`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ResponseEvidence {
+    /// Hex encoded SHA-256 of the raw HTTP response body.
+    pub body_sha256: String,
+    pub status_code: u16,
+    /// Time (ms) the response was received.
+    pub response_timestamp: u64,
+    /// Opaque transcript proof (e.g. TLS-notary), not interpreted by the contract.
+    pub proof: Option<Base64VecU8>,
+}
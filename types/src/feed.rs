@@ -0,0 +1,71 @@
+use crate::*;
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+
+/// How operators obtain a feed value, in the same shape as `create_oracle` arguments.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeedSpec {
+    pub url: String,
+    pub data: String,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Round {
+    pub round_id: u64,
+    pub answer: U128,
+    /// Block time (ms) the round was recorded.
+    pub timestamp: u64,
+    pub operator: AccountId,
+}
+
+/// A standing data feed (e.g. NEAR/USD) that operators keep updating with new rounds.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Feed {
+    pub id: String,
+    pub spec: FeedSpec,
+    /// Maximum time between rounds.
+    pub heartbeat_secs: u64,
+    /// Change from the latest answer, in basis points, that justifies a round before the
+    /// heartbeat is due.
+    pub deviation_threshold_bps: u32,
+    /// Answers are fixed point integers with this many decimals.
+    pub decimals: u8,
+    pub latest_round: Option<Round>,
+    /// Number of most recent rounds kept on-chain.
+    pub history_size: u32,
+    /// Account whose storage balance pays for the feed and its round history: the owner
+    /// that created it.
+    pub sponsor: AccountId,
+}
+
+/// Deviation of `answer` from `previous` in basis points, saturating on overflow.
+pub fn deviation_bps(previous: u128, answer: u128) -> u128 {
+    if previous == 0 {
+        return if answer == 0 { 0 } else { u128::MAX };
+    }
+    answer
+        .abs_diff(previous)
+        .checked_mul(10_000)
+        .map_or(u128::MAX, |diff| diff / previous)
+}
+
+impl Feed {
+    /// Whether a new `answer` may be recorded at `now` (ms).
+    pub fn is_round_due(&self, answer: u128, now: u64) -> bool {
+        match &self.latest_round {
+            None => true,
+            Some(latest) => {
+                now.saturating_sub(latest.timestamp) >= self.heartbeat_secs.saturating_mul(1000)
+                    || deviation_bps(latest.answer.0, answer)
+                        >= self.deviation_threshold_bps as u128
+            }
+        }
+    }
+    /// Key of the ring buffer slot holding `round_id`.
+    pub fn round_key(&self, round_id: u64) -> (String, u64) {
+        (self.id.clone(), round_id % self.history_size as u64)
+    }
+}
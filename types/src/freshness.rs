@@ -0,0 +1,20 @@
+use crate::*;
+
+/// A request result or feed answer to read with a freshness bound.
+/// Serialized as `{ "request": "<id>" }` or `{ "feed": "<feed id>" }`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum DataRef {
+    Request(String),
+    Feed(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FreshValue {
+    /// Request `return_value`, or the feed answer as a decimal integer string.
+    pub value: String,
+    /// Time (ms) the value was written.
+    pub updated_at: u64,
+}
@@ -0,0 +1,23 @@
+//! The stable consumer-facing API of the oracle contract. Consumers call it through the
+//! generated `ext_oracle` module, e.g. `ext_oracle::ext(oracle_id).get_result(id)`; the
+//! contract implements [`OracleV1`] itself, so the two cannot drift apart.
+//!
+//! Only built with the `interface` feature, as off-chain clients have no use for promises.
+
+use crate::*;
+use near_sdk::ext_contract;
+
+/// Interface name reported by `supports_interface` for [`OracleV1`].
+pub const ORACLE_INTERFACE: &str = "oracle";
+pub const ORACLE_INTERFACE_VERSION: u32 = 1;
+
+/// New methods go in a new versioned trait rather than changing this one.
+#[ext_contract(ext_oracle)]
+pub trait OracleV1 {
+    /// Queues a request, attaching at least `fee_per_call`. Same as `create_oracle`.
+    fn request(&mut self, url: String, data: String, callback: Option<Callback>) -> OracleData;
+    fn get_result(&self, id: String) -> Option<OracleResult>;
+    /// Latest round of a feed.
+    fn get_latest(&self, feed_id: String) -> Option<Round>;
+    fn supports_interface(&self, name: String, version: u32) -> bool;
+}
@@ -0,0 +1,41 @@
+//! Public types of the oracle contract, shared by the contract and its off-chain clients.
+//!
+//! JSON (de)serialization matches the contract's view and call arguments; Borsh matches its
+//! state layout.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+pub mod attestation;
+pub mod awaiting;
+pub mod callback;
+pub mod client;
+pub mod data;
+pub mod encoding;
+pub mod event;
+pub mod evidence;
+pub mod feed;
+pub mod freshness;
+#[cfg(feature = "interface")]
+pub mod interface;
+pub mod randomness;
+pub mod report;
+pub mod storage;
+pub mod subscription;
+pub mod transform;
+
+pub use attestation::Attestation;
+pub use awaiting::{AwaitError, AwaitOutcome};
+pub use callback::{Callback, DeliveryStatus};
+pub use data::{OracleData, OracleRequest, OracleResult, Url};
+pub use encoding::{ResultEncoding, ValueType};
+pub use event::{OracleEvent, OracleEventKind};
+pub use evidence::ResponseEvidence;
+pub use feed::{Feed, FeedSpec, Round};
+pub use freshness::{DataRef, FreshValue};
+#[cfg(feature = "interface")]
+pub use interface::{ext_oracle, OracleV1};
+pub use randomness::{RandomnessFulfillment, RandomnessRequest};
+pub use report::SignedReport;
+pub use storage::{StorageBalance, StorageBalanceBounds};
+pub use subscription::Subscription;
@@ -0,0 +1,55 @@
+use crate::callback::{Callback, DeliveryStatus};
+use crate::*;
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::{AccountId, PublicKey};
+
+/// A request for a random value, fulfilled by the data provider assigned to it.
+///
+/// The provider proves [`randomness_alpha`] of (id, seed, `block_seed`) with the
+/// ECVRF-EDWARDS25519-SHA512-TAI VRF (RFC 9381) under its registered ed25519 key, and the
+/// released value is the first 32 bytes of the VRF output. A VRF has one valid output per
+/// key and input, so neither who relays the proof nor when it lands changes the value.
+///
+/// Trust model: the producer of the request block does not hold the provider key, so it
+/// cannot predict the value, and NEAR derives `block_seed` from the producer's own VRF, so it
+/// can only influence it by not producing the block. The provider cannot choose the value,
+/// but it learns it first and can withhold its proof; providers are assigned round robin
+/// when the request is made, so it cannot hand the request to another provider either. A
+/// provider colluding with the producer of the request block learns the value before that
+/// block is produced, and the pair can bias it by withholding the block. Do not use it where
+/// such a pair gains more from bias than from honest operation. A request whose provider
+/// is removed, or does not answer for the contract's `RANDOMNESS_CANCEL_AFTER_BLOCKS`, can
+/// be cancelled by its requester for a refund.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RandomnessRequest {
+    pub id: String,
+    pub requester: AccountId,
+    pub seed: Base64VecU8,
+    /// `env::random_seed()` of the block the request was made in.
+    pub block_seed: Base64VecU8,
+    pub block_height: u64,
+    /// Key of the data provider whose VRF proof fulfils the request.
+    pub provider: PublicKey,
+    /// Deposit paid for the request, refunded if it is cancelled.
+    pub fee: U128,
+    pub callback: Callback,
+    pub fulfillment: Option<RandomnessFulfillment>,
+    /// Delivery of the value to `callback`.
+    pub delivery: Option<DeliveryStatus>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RandomnessFulfillment {
+    /// VRF proof (`Gamma || c || s`) over [`randomness_alpha`].
+    pub proof: Base64VecU8,
+    /// Hex encoded first 32 bytes of the VRF output.
+    pub value: String,
+    pub fulfilled_at: u64,
+}
+
+/// VRF input a provider proves: the borsh `(id, seed, block_seed)` tuple.
+pub fn randomness_alpha(id: &str, seed: &[u8], block_seed: &[u8]) -> Vec<u8> {
+    (id, seed, block_seed).try_to_vec().unwrap()
+}
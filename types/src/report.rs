@@ -0,0 +1,13 @@
+use crate::*;
+
+/// Value for a queued request signed by an external secp256k1 publisher.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SignedReport {
+    pub id: String,
+    pub value: String,
+    /// Time (ms) the publisher observed the value.
+    pub timestamp: u64,
+    /// Hex encoded 65-byte `r || s || v` signature, `v` either 0/1 or 27/28.
+    pub signature: String,
+}
@@ -0,0 +1,33 @@
+use crate::*;
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+
+/// A prepaid recurring request: operators fulfil it every `interval_secs`, each run is paid
+/// for with `fee_per_call` out of `balance` and overwrites `result`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Subscription {
+    pub id: String,
+    pub owner: AccountId,
+    pub spec: FeedSpec,
+    pub interval_secs: u64,
+    pub max_runs: u32,
+    pub runs: u32,
+    pub balance: U128,
+    /// Time (ms) of the last run.
+    pub last_run: Option<u64>,
+    pub result: Option<String>,
+    pub callback: Option<Callback>,
+    /// Delivery of the latest run's value to `callback`.
+    pub delivery: Option<DeliveryStatus>,
+}
+
+impl Subscription {
+    /// Whether a run may be recorded at `now` (ms), ignoring the balance.
+    pub fn is_due(&self, now: u64) -> bool {
+        self.runs < self.max_runs
+            && self.last_run.is_none_or(|last_run| {
+                now >= last_run.saturating_add(self.interval_secs.saturating_mul(1000))
+            })
+    }
+}
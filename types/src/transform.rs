@@ -0,0 +1,23 @@
+//! Limits shared by every operator running a WASM transform, so they all get the same result
+//! or the same failure.
+//!
+//! A transform module imports nothing and exports `memory`, `alloc(len: i32) -> i32` and
+//! `transform(ptr: i32, len: i32) -> i64`. The node writes the response document at the
+//! pointer `alloc` returns and calls `transform`, whose result packs the output as
+//! `ptr << 32 | len`. The output must be UTF-8; it is submitted as the request's value.
+
+/// Largest module `register_transform` accepts, in bytes.
+pub const MAX_TRANSFORM_SIZE: usize = 128 * 1024;
+
+/// Fuel a transform may consume, instantiation included.
+pub const TRANSFORM_FUEL: u64 = 10_000_000;
+
+/// Longest output a transform may return, in bytes.
+pub const MAX_TRANSFORM_OUTPUT: usize = 4 * 1024;
+
+/// Largest linear memory a transform may declare or grow to, in bytes. Growing past it
+/// makes `memory.grow` return -1.
+pub const MAX_TRANSFORM_MEMORY: usize = 16 * 1024 * 1024;
+
+/// Most elements a transform's table may hold.
+pub const MAX_TRANSFORM_TABLE_ELEMENTS: usize = 1024;